@@ -21,7 +21,7 @@ use grandpa::{AuthorityId as GrandpaId, AuthorityWeight as GrandpaWeight};
 use grandpa::fg_primitives;
 use client::{
 	block_builder::api::{CheckInherentsResult, InherentData, self as block_builder_api},
-	runtime_api as client_api, impl_runtime_apis
+	runtime_api as client_api, impl_runtime_apis, decl_runtime_apis
 };
 use version::RuntimeVersion;
 #[cfg(feature = "std")]
@@ -62,7 +62,7 @@ pub type Hash = primitives::H256;
 pub type DigestItem = generic::DigestItem<Hash>;
 
 /// Custom runtime modules
-mod name_service;
+pub mod name_service;
 mod identity;
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
@@ -98,8 +98,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("substrate-name-service"),
 	impl_name: create_runtime_str!("substrate-name-service"),
 	authoring_version: 3,
-	spec_version: 4,
-	impl_version: 4,
+	spec_version: 8,
+	impl_version: 6,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -253,9 +253,53 @@ impl sudo::Trait for Runtime {
 	type Proposal = Call;
 }
 
+parameter_types! {
+	pub const RegistrationPeriod: BlockNumber = 365 * DAYS;
+	pub const AuctionPeriod: BlockNumber = 1 * HOURS;
+	pub const RevealPeriod: BlockNumber = 1 * HOURS;
+	pub const EndingPeriod: BlockNumber = 10 * MINUTES;
+	pub const GracePeriod: BlockNumber = 90 * DAYS;
+	pub const PremiumPeriod: BlockNumber = 21 * DAYS;
+	// 100 DEV
+	pub const PremiumStartPrice: Balance = 100_000_000_000_000;
+	pub const UnrevealedBidPenalty: Perbill = Perbill::from_percent(10);
+	// 0.001 DEV
+	pub const BasePrice: Balance = 1_000_000_000;
+	pub const MinCommitmentAge: BlockNumber = 1 * MINUTES;
+	pub const MaxCommitmentAge: BlockNumber = 1 * DAYS;
+	pub const TransferPeriod: BlockNumber = 7 * DAYS;
+	pub const MaxTextKeyLength: u32 = 64;
+	pub const MaxTextValueLength: u32 = 1024;
+	// 0.00001 DEV
+	pub const TextByteDeposit: Balance = 10_000_000;
+	pub const MaxAddresses: u32 = 8;
+}
+
 /// Used for the module template in `./name_service.rs`
 impl name_service::Trait for Runtime {
 	type Event = Event;
+	type Currency = Balances;
+	type PriceOracle = name_service::LengthPricing<Runtime>;
+	/// A domain is registered for a year of blocks
+	type RegistrationPeriod = RegistrationPeriod;
+	type AuctionPeriod = AuctionPeriod;
+	type RevealPeriod = RevealPeriod;
+	type EndingPeriod = EndingPeriod;
+	type GracePeriod = GracePeriod;
+	type PremiumPeriod = PremiumPeriod;
+	type PremiumStartPrice = PremiumStartPrice;
+	type PremiumDecay = name_service::LinearDecay;
+	/// The premiums of expired domains are burned
+	type PremiumProceeds = ();
+	type UnrevealedBidPenalty = UnrevealedBidPenalty;
+	type BasePrice = BasePrice;
+	type MinCommitmentAge = MinCommitmentAge;
+	type MaxCommitmentAge = MaxCommitmentAge;
+	type TransferPeriod = TransferPeriod;
+	type MaxTextKeyLength = MaxTextKeyLength;
+	type MaxTextValueLength = MaxTextValueLength;
+	type TextByteDeposit = TextByteDeposit;
+	type MaxAddresses = MaxAddresses;
 }
 
 impl identity::Trait for Runtime {
@@ -307,6 +351,38 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExt
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Runtime, Block, system::ChainContext<Runtime>, Runtime, AllModules>;
 
+/// Domain as returned by the `NameServiceApi`
+pub type DomainInfo = name_service::DomainInfo<AccountId, Balance, BlockNumber, Hash>;
+/// Auction of a domain as returned by the `NameServiceApi`
+pub type AuctionStatus = name_service::AuctionStatus<AccountId, Balance, BlockNumber>;
+pub use name_service::DomainState;
+
+decl_runtime_apis! {
+	/// Read-only queries of the name service, which clients can make without sending a transaction
+	pub trait NameServiceApi {
+		/// Domain with the normalized name, `None` if the name is invalid or not registered
+		fn resolve(name: Vec<u8>) -> Option<DomainInfo>;
+		/// Domain with the namehash
+		fn resolve_hash(domain_hash: Hash) -> Option<DomainInfo>;
+		/// Page of the domains owned by the account, see `name_service::Module::domains_of`
+		fn reverse(account: AccountId, start: u64, limit: u64) -> Vec<Hash>;
+		/// Primary name of the account, if it still resolves to the account
+		fn primary_name(account: AccountId) -> Option<Hash>;
+		/// Lifecycle state of the domain, `Available` if it is not registered
+		fn state(domain_hash: Hash) -> DomainState;
+		/// State of the auction of the domain, if it is in one
+		fn auction_status(domain_hash: Hash) -> Option<AuctionStatus>;
+		/// Text records of the domain as (key, value) pairs, empty once the domain stops resolving
+		fn texts(domain_hash: Hash) -> Vec<(Vec<u8>, Vec<u8>)>;
+		/// DNS TTL and RDATA of the records of the type, see `name_service::Module::resolve_dns`
+		fn dns_records(domain_hash: Hash, rtype: u16) -> Option<(u32, Vec<Vec<u8>>)>;
+		/// Address of the domain on the chain with the SLIP-44 coin type, see `name_service::address`
+		fn address(domain_hash: Hash, coin_type: u32) -> Option<Vec<u8>>;
+		/// EIP-1577 content hash of the domain, `None` once the domain stops resolving
+		fn content_hash(domain_hash: Hash) -> Option<Vec<u8>>;
+	}
+}
+
 impl_runtime_apis! {
 	impl client_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -386,6 +462,49 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl self::NameServiceApi<Block> for Runtime {
+		fn resolve(name: Vec<u8>) -> Option<DomainInfo> {
+			name_service::name::validate_owner(&name).ok()?;
+			NameService::domain_info(NameService::domain_hash_of(&name))
+		}
+
+		fn resolve_hash(domain_hash: Hash) -> Option<DomainInfo> {
+			NameService::domain_info(domain_hash)
+		}
+
+		fn reverse(account: AccountId, start: u64, limit: u64) -> Vec<Hash> {
+			NameService::domains_of(account, start, limit)
+		}
+
+		fn primary_name(account: AccountId) -> Option<Hash> {
+			NameService::primary_name(account)
+		}
+
+		fn state(domain_hash: Hash) -> DomainState {
+			NameService::domain_state(domain_hash)
+		}
+
+		fn auction_status(domain_hash: Hash) -> Option<AuctionStatus> {
+			NameService::auction_status(domain_hash)
+		}
+
+		fn texts(domain_hash: Hash) -> Vec<(Vec<u8>, Vec<u8>)> {
+			NameService::resolve_texts(domain_hash)
+		}
+
+		fn dns_records(domain_hash: Hash, rtype: u16) -> Option<(u32, Vec<Vec<u8>>)> {
+			NameService::resolve_dns(domain_hash, rtype)
+		}
+
+		fn address(domain_hash: Hash, coin_type: u32) -> Option<Vec<u8>> {
+			NameService::resolve_address(domain_hash, coin_type)
+		}
+
+		fn content_hash(domain_hash: Hash) -> Option<Vec<u8>> {
+			NameService::resolve_content_hash(domain_hash)
+		}
+	}
+
 	impl substrate_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			let seed = seed.as_ref().map(|s| rstd::str::from_utf8(&s).expect("Seed is an utf8 string"));
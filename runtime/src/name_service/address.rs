@@ -0,0 +1,282 @@
+//! Coin types and validation of the chain addresses a domain resolves to.
+//!
+//! Coin types are the SLIP-44 ids (https://github.com/satoshilabs/slips/blob/master/slip-0044.md), which
+//! ENS uses for its multi-chain address records as well. Addresses are stored as raw bytes in the
+//! format of EIP-2304: the 32 byte account id for SS58 chains, the 20 byte address for EVM chains, and the
+//! scriptPubkey for Bitcoin style chains. The base58check and bech32(m) address strings of Bitcoin style
+//! chains are decoded to their scriptPubkey when they are set. Other coin types are only checked for their length.
+
+use rstd::prelude::*;
+use rstd::result;
+
+pub const BTC: u32 = 0;
+pub const LTC: u32 = 2;
+pub const ETH: u32 = 60;
+pub const ETC: u32 = 61;
+pub const DOT: u32 = 354;
+pub const KSM: u32 = 434;
+
+/// Maximum length of an address in bytes
+pub const MAX_ADDRESS_LENGTH: usize = 128;
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+/// Checksum constant of bech32(BIP-173), used by segwit version 0
+const BECH32_CONST: u32 = 1;
+/// Checksum constant of bech32m(BIP-350), used by segwit version 1 and above e.g. taproot
+const BECH32M_CONST: u32 = 0x2bc830a3;
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Address formats of a Bitcoin style chain
+struct Bitcoin {
+	/// human readable part of its segwit addresses
+	hrp: &'static [u8],
+	/// version bytes of its base58check P2PKH addresses
+	p2pkh: &'static [u8],
+	/// version bytes of its base58check P2SH addresses
+	p2sh: &'static [u8],
+}
+
+const BITCOIN: Bitcoin = Bitcoin { hrp: b"bc", p2pkh: &[0x00], p2sh: &[0x05] };
+/// Litecoin, including the P2SH version it shared with Bitcoin
+const LITECOIN: Bitcoin = Bitcoin { hrp: b"ltc", p2pkh: &[0x30], p2sh: &[0x32, 0x05] };
+
+const OP_0: u8 = 0x00;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_DUP: u8 = 0x76;
+const OP_HASH160: u8 = 0xa9;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_CHECKSIG: u8 = 0xac;
+
+/// Raw bytes to store for the address of the chain with the coin type, after checking its format.
+/// Bitcoin style addresses are either a standard scriptPubkey or an address string, which is decoded to its scriptPubkey
+pub fn to_raw(coin_type: u32, address: &[u8]) -> result::Result<Vec<u8>, &'static str> {
+	if address.is_empty() {
+		return Err("The address is empty");
+	}
+	if address.len() > MAX_ADDRESS_LENGTH {
+		return Err("The address is too long");
+	}
+	match coin_type {
+		DOT | KSM if address.len() != 32 => Err("The address of an SS58 chain must be a 32 byte account id"),
+		ETH | ETC if address.len() != 20 => Err("The address of an EVM chain must be 20 bytes"),
+		BTC => bitcoin_script(&BITCOIN, address),
+		LTC => bitcoin_script(&LITECOIN, address),
+		_ => Ok(address.to_vec()),
+	}
+}
+
+/// scriptPubkey of the address of a Bitcoin style chain, which is either a standard scriptPubkey already,
+/// a segwit address with the human readable part of the chain or a base58check address with one of its version bytes
+fn bitcoin_script(chain: &Bitcoin, address: &[u8]) -> result::Result<Vec<u8>, &'static str> {
+	// The second byte of a standard script is a push of at most 40 bytes, which is never a character of an address string
+	if is_standard_script(address) {
+		return Ok(address.to_vec());
+	}
+	let hrp = chain.hrp;
+	let is_segwit = address.len() > hrp.len() && address.starts_with(hrp) && address[hrp.len()] == b'1';
+	if is_segwit || !address.iter().all(|c| BASE58_ALPHABET.contains(c)) {
+		// Segwit addresses of other chains are rejected for their human readable part
+		return decode_bech32(hrp, address);
+	}
+	decode_base58check(chain, address)
+}
+
+/// Whether the script is a P2PKH, P2SH or segwit scriptPubkey
+fn is_standard_script(script: &[u8]) -> bool {
+	match script.len() {
+		25 if script[..3] == [OP_DUP, OP_HASH160, 20] && script[23..] == [OP_EQUALVERIFY, OP_CHECKSIG] => true,
+		23 if script[..2] == [OP_HASH160, 20] && script[22] == OP_EQUAL => true,
+		len if len >= 4 => {
+			let program = len - 2;
+			let version = script[0];
+			script[1] as usize == program
+				&& ((version == OP_0 && (program == 20 || program == 32)) || ((OP_1..=OP_16).contains(&version) && program <= 40))
+		}
+		_ => false,
+	}
+}
+
+/// scriptPubkey of a base58check address of a 20 byte hash with one of the version bytes of the chain
+fn decode_base58check(chain: &Bitcoin, address: &[u8]) -> result::Result<Vec<u8>, &'static str> {
+	let decoded = base58_decode(address).ok_or("The address is not base58")?;
+	// version byte, 20 byte hash and 4 byte checksum
+	if decoded.len() != 25 {
+		return Err("The address is not base58check");
+	}
+	let (payload, checksum) = decoded.split_at(21);
+	if sha256(&sha256(payload))[..4] != *checksum {
+		return Err("The checksum of the address is invalid");
+	}
+
+	let (version, hash) = (payload[0], &payload[1..]);
+	let mut script = Vec::with_capacity(25);
+	if chain.p2pkh.contains(&version) {
+		script.extend_from_slice(&[OP_DUP, OP_HASH160, 20]);
+		script.extend_from_slice(hash);
+		script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+	} else if chain.p2sh.contains(&version) {
+		script.extend_from_slice(&[OP_HASH160, 20]);
+		script.extend_from_slice(hash);
+		script.push(OP_EQUAL);
+	} else {
+		return Err("The address is for another chain");
+	}
+	Ok(script)
+}
+
+/// scriptPubkey of a lowercase segwit address with the human readable part and a valid checksum,
+/// bech32 for witness version 0 and bech32m for the later versions
+fn decode_bech32(hrp: &[u8], address: &[u8]) -> result::Result<Vec<u8>, &'static str> {
+	let separator = address.iter().rposition(|c| *c == b'1').ok_or("The address is not bech32")?;
+	if &address[..separator] != hrp {
+		return Err("The address is for another chain");
+	}
+	let data = &address[separator + 1..];
+	// at least the witness version and the 6 characters of the checksum
+	if data.len() < 7 || address.len() > 90 {
+		return Err("The address is not bech32");
+	}
+	let mut values = Vec::with_capacity(hrp.len() * 2 + 1 + data.len());
+	values.extend(hrp.iter().map(|c| c >> 5));
+	values.push(0);
+	values.extend(hrp.iter().map(|c| c & 31));
+	let start = values.len();
+	for c in data {
+		let value = BECH32_CHARSET.iter().position(|b| b == c).ok_or("The address is not bech32")?;
+		values.push(value as u8);
+	}
+	let version = values[start];
+	let checksum_const = match version {
+		0 => BECH32_CONST,
+		1..=16 => BECH32M_CONST,
+		_ => return Err("The witness version of the address is invalid"),
+	};
+	if polymod(&values) != checksum_const {
+		return Err("The checksum of the address is invalid");
+	}
+
+	// The witness program is packed in 5 bit groups between the version and the checksum
+	let mut program = Vec::with_capacity(40);
+	let (mut acc, mut bits) = (0u32, 0u32);
+	for value in &values[start + 1..values.len() - 6] {
+		acc = ((acc << 5) | u32::from(*value)) & 0xfff;
+		bits += 5;
+		if bits >= 8 {
+			bits -= 8;
+			program.push((acc >> bits) as u8);
+		}
+	}
+	if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+		return Err("The witness program of the address is invalid");
+	}
+	if program.len() < 2 || program.len() > 40 || (version == 0 && program.len() != 20 && program.len() != 32) {
+		return Err("The witness program of the address is invalid");
+	}
+
+	let mut script = Vec::with_capacity(program.len() + 2);
+	script.push(if version == 0 { OP_0 } else { OP_1 + version - 1 });
+	script.push(program.len() as u8);
+	script.extend(program);
+	Ok(script)
+}
+
+/// Bytes of the base58 string, keeping its leading zeros
+fn base58_decode(input: &[u8]) -> Option<Vec<u8>> {
+	// big endian digits of the number in base 256
+	let mut bytes: Vec<u8> = Vec::with_capacity(input.len());
+	for c in input {
+		let mut carry = BASE58_ALPHABET.iter().position(|b| b == c)? as u32;
+		for byte in bytes.iter_mut().rev() {
+			carry += u32::from(*byte) * 58;
+			*byte = carry as u8;
+			carry >>= 8;
+		}
+		while carry > 0 {
+			bytes.insert(0, carry as u8);
+			carry >>= 8;
+		}
+	}
+	let zeros = input.iter().take_while(|c| **c == b'1').count();
+	let mut decoded = vec![0u8; zeros];
+	decoded.extend(bytes);
+	Some(decoded)
+}
+
+const SHA256_K: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 of the data, which base58check uses for its checksum. The runtime has no host function for it
+fn sha256(data: &[u8]) -> [u8; 32] {
+	let mut state: [u32; 8] = [
+		0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+	];
+	let mut message = data.to_vec();
+	message.push(0x80);
+	while message.len() % 64 != 56 {
+		message.push(0);
+	}
+	message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+	for block in message.chunks(64) {
+		let mut w = [0u32; 64];
+		for (i, word) in block.chunks(4).enumerate() {
+			w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+		}
+		for i in 16..64 {
+			let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+			let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+			w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+		}
+		let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+		for i in 0..64 {
+			let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+			let ch = (e & f) ^ (!e & g);
+			let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+			let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+			let maj = (a & b) ^ (a & c) ^ (b & c);
+			let t2 = s0.wrapping_add(maj);
+			h = g;
+			g = f;
+			f = e;
+			e = d.wrapping_add(t1);
+			d = c;
+			c = b;
+			b = a;
+			a = t1.wrapping_add(t2);
+		}
+		for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+			*s = s.wrapping_add(*v);
+		}
+	}
+
+	let mut digest = [0u8; 32];
+	for (i, word) in state.iter().enumerate() {
+		digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+	}
+	digest
+}
+
+fn polymod(values: &[u8]) -> u32 {
+	let mut checksum = 1u32;
+	for value in values {
+		let top = checksum >> 25;
+		checksum = ((checksum & 0x1ff_ffff) << 5) ^ u32::from(*value);
+		for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+			if (top >> i) & 1 == 1 {
+				checksum ^= generator;
+			}
+		}
+	}
+	checksum
+}
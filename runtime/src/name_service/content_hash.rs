@@ -0,0 +1,91 @@
+//! Validation of the content hash records of domains, which point at websites and files on decentralized storage.
+//!
+//! The encoding is the one of EIP-1577 so that ENS tooling and gateways can read it as is:
+//! > content_hash = uvarint(namespace multicodec) ++ value
+//! IPFS values are CIDv1 (or a bare CIDv0 multihash), Swarm values are CIDv1 with the swarm-manifest codec
+//! and a keccak-256 multihash, and Arweave values are the 32 byte transaction id.
+
+use support::dispatch::Result;
+
+/// Multicodec of the IPFS namespace
+pub const IPFS_NS: u64 = 0xe3;
+/// Multicodec of the Swarm namespace
+pub const SWARM_NS: u64 = 0xe4;
+/// Multicodec of the Arweave namespace
+pub const ARWEAVE_NS: u64 = 0xb29910;
+
+/// Maximum length of a content hash in bytes
+pub const MAX_CONTENT_HASH_LENGTH: usize = 128;
+
+const SHA2_256: u64 = 0x12;
+const KECCAK_256: u64 = 0x1b;
+const SWARM_MANIFEST: u64 = 0xfa;
+
+/// Ensure that the content hash is a supported namespace followed by a well formed value
+pub fn validate(content_hash: &[u8]) -> Result {
+	if content_hash.len() > MAX_CONTENT_HASH_LENGTH {
+		return Err("The content hash is too long");
+	}
+	let (namespace, value) = read_varint(content_hash)?;
+	match namespace {
+		IPFS_NS => validate_ipfs(value),
+		SWARM_NS => {
+			let (codec, multihash) = read_cid_v1(value)?;
+			if codec != SWARM_MANIFEST {
+				return Err("The Swarm content hash is not a manifest");
+			}
+			validate_multihash(multihash, Some(KECCAK_256))
+		}
+		ARWEAVE_NS if value.len() == 32 => Ok(()),
+		ARWEAVE_NS => Err("The Arweave content hash must be a 32 byte transaction id"),
+		_ => Err("The namespace of the content hash is not supported"),
+	}
+}
+
+fn validate_ipfs(value: &[u8]) -> Result {
+	// CIDv0 is a bare sha2-256 multihash, which starts with 0x12 0x20
+	if value.first() == Some(&(SHA2_256 as u8)) {
+		return validate_multihash(value, Some(SHA2_256));
+	}
+	let (_, multihash) = read_cid_v1(value)?;
+	validate_multihash(multihash, None)
+}
+
+/// Read the version and the content codec of a CIDv1, returning the codec and the multihash
+fn read_cid_v1(cid: &[u8]) -> rstd::result::Result<(u64, &[u8]), &'static str> {
+	let (version, rest) = read_varint(cid)?;
+	if version != 1 {
+		return Err("The content hash is not a CIDv1");
+	}
+	read_varint(rest)
+}
+
+/// Ensure that the multihash has a digest of the length it declares, and of the hash function if given
+fn validate_multihash(multihash: &[u8], function: Option<u64>) -> Result {
+	let (code, rest) = read_varint(multihash)?;
+	let (length, digest) = read_varint(rest)?;
+	if function.map_or(false, |function| function != code) {
+		return Err("The content hash uses an unexpected hash function");
+	}
+	if (code == SHA2_256 || code == KECCAK_256) && length != 32 {
+		return Err("The digest of the content hash has a wrong length");
+	}
+	if length == 0 || digest.len() as u64 != length {
+		return Err("The digest of the content hash has a wrong length");
+	}
+
+	Ok(())
+}
+
+/// Read an unsigned LEB128 varint as used by multiformats, returning it and the rest of the bytes
+fn read_varint(bytes: &[u8]) -> rstd::result::Result<(u64, &[u8]), &'static str> {
+	let mut value = 0u64;
+	// multiformats varints are at most 9 bytes
+	for (i, byte) in bytes.iter().enumerate().take(9) {
+		value |= u64::from(byte & 0x7f) << (7 * i);
+		if byte & 0x80 == 0 {
+			return Ok((value, &bytes[i + 1..]));
+		}
+	}
+	Err("The content hash is not a valid multiformat")
+}
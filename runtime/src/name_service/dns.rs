@@ -0,0 +1,217 @@
+//! Typed DNS resource records of domains and their wire format.
+//!
+//! Record sets are stored as the RDATA of RFC 1035 with uncompressed names, which is what a DNS server
+//! puts into its answers, so that a gateway can serve them unchanged. Every RDATA is parsed into a
+//! `RecordData` on write and has to encode back to the exact same bytes, so only the canonical form is stored.
+//!
+//! The record sets of services, like the SRV records of `_sip._tcp.company.dot`, belong to subdomains with
+//! service labels, which the source of a domain creates like any other subdomain.
+
+use support::dispatch::Result;
+use rstd::prelude::*;
+use super::name;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_MX: u16 = 15;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_TLSA: u16 = 52;
+
+/// TTL of record sets in seconds, when the controller does not set one
+pub const DEFAULT_TTL: u32 = 300;
+/// Maximum number of records in a record set
+pub const MAX_RECORDS: usize = 16;
+/// Maximum length of the RDATA of a record in bytes
+pub const MAX_RDATA_LENGTH: usize = 512;
+/// Maximum number of CNAMEs followed from a domain, RFC 1034 only asks to stop at loops
+pub const MAX_CNAME_CHAIN: usize = 8;
+
+/// Data of a resource record
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum RecordData {
+	/// Alias of the domain, the canonical name
+	Cname(Vec<u8>),
+	/// Mail exchange, lower preferences are tried first. An empty exchange is the null MX of RFC 7505
+	Mx { preference: u16, exchange: Vec<u8> },
+	/// Service location of RFC 2782. An empty target means that the service is not available
+	Srv { priority: u16, weight: u16, port: u16, target: Vec<u8> },
+	/// Character strings of at most 255 bytes each
+	Txt(Vec<Vec<u8>>),
+	/// DANE certificate association of RFC 6698
+	Tlsa { usage: u8, selector: u8, matching_type: u8, data: Vec<u8> },
+}
+
+impl RecordData {
+	/// Type of the record
+	pub fn rtype(&self) -> u16 {
+		match self {
+			RecordData::Cname(_) => TYPE_CNAME,
+			RecordData::Mx { .. } => TYPE_MX,
+			RecordData::Srv { .. } => TYPE_SRV,
+			RecordData::Txt(_) => TYPE_TXT,
+			RecordData::Tlsa { .. } => TYPE_TLSA,
+		}
+	}
+
+	/// RDATA of the record in the wire format
+	pub fn to_wire(&self) -> Vec<u8> {
+		let mut rdata = Vec::new();
+		match self {
+			RecordData::Cname(target) => encode_name(target, &mut rdata),
+			RecordData::Mx { preference, exchange } => {
+				rdata.extend_from_slice(&preference.to_be_bytes());
+				encode_name(exchange, &mut rdata);
+			}
+			RecordData::Srv { priority, weight, port, target } => {
+				rdata.extend_from_slice(&priority.to_be_bytes());
+				rdata.extend_from_slice(&weight.to_be_bytes());
+				rdata.extend_from_slice(&port.to_be_bytes());
+				encode_name(target, &mut rdata);
+			}
+			RecordData::Txt(strings) => {
+				for string in strings {
+					rdata.push(string.len() as u8);
+					rdata.extend_from_slice(string);
+				}
+			}
+			RecordData::Tlsa { usage, selector, matching_type, data } => {
+				rdata.extend_from_slice(&[*usage, *selector, *matching_type]);
+				rdata.extend_from_slice(data);
+			}
+		}
+		rdata
+	}
+
+	/// Parse the RDATA of a record of the type
+	pub fn from_wire(rtype: u16, rdata: &[u8]) -> rstd::result::Result<Self, &'static str> {
+		let data = match rtype {
+			TYPE_CNAME => {
+				let (target, rest) = decode_name(rdata)?;
+				ensure_end(rest)?;
+				if target.is_empty() {
+					return Err("The CNAME target is empty");
+				}
+				RecordData::Cname(target)
+			}
+			TYPE_MX => {
+				let (preference, rest) = read_u16(rdata)?;
+				let (exchange, rest) = decode_name(rest)?;
+				ensure_end(rest)?;
+				RecordData::Mx { preference, exchange }
+			}
+			TYPE_SRV => {
+				let (priority, rest) = read_u16(rdata)?;
+				let (weight, rest) = read_u16(rest)?;
+				let (port, rest) = read_u16(rest)?;
+				let (target, rest) = decode_name(rest)?;
+				ensure_end(rest)?;
+				RecordData::Srv { priority, weight, port, target }
+			}
+			TYPE_TXT => {
+				let mut strings = Vec::new();
+				let mut rest = rdata;
+				while let Some((len, tail)) = rest.split_first() {
+					let len = *len as usize;
+					if tail.len() < len {
+						return Err("The DNS record is truncated");
+					}
+					strings.push(tail[..len].to_vec());
+					rest = &tail[len..];
+				}
+				if strings.is_empty() {
+					return Err("The TXT record has no character string");
+				}
+				RecordData::Txt(strings)
+			}
+			TYPE_TLSA => {
+				if rdata.len() < 4 {
+					return Err("The DNS record is truncated");
+				}
+				let (usage, selector, matching_type) = (rdata[0], rdata[1], rdata[2]);
+				let data = rdata[3..].to_vec();
+				if usage > 3 || selector > 1 || matching_type > 2 {
+					return Err("The TLSA record has an unknown usage, selector or matching type");
+				}
+				// SHA-256 and SHA-512 digests
+				if (matching_type == 1 && data.len() != 32) || (matching_type == 2 && data.len() != 64) {
+					return Err("The TLSA digest has a wrong length");
+				}
+				RecordData::Tlsa { usage, selector, matching_type, data }
+			}
+			TYPE_A | TYPE_AAAA => return Err("A and AAAA records are set with the IPV4 and IPV6 calls"),
+			_ => return Err("The DNS record type is not supported"),
+		};
+		Ok(data)
+	}
+}
+
+/// Ensure that the RDATA is a canonical record of the type
+pub fn validate(rtype: u16, rdata: &[u8]) -> Result {
+	if rdata.len() > MAX_RDATA_LENGTH {
+		return Err("The DNS record is too long");
+	}
+	if RecordData::from_wire(rtype, rdata)?.to_wire() != rdata {
+		return Err("The DNS record is not in the canonical wire format");
+	}
+
+	Ok(())
+}
+
+/// Append the name in the wire format, a length prefixed label at a time and the empty root label
+pub fn encode_name(name: &[u8], out: &mut Vec<u8>) {
+	if !name.is_empty() {
+		for label in name.split(|c| *c == b'.') {
+			out.push(label.len() as u8);
+			out.extend_from_slice(label);
+		}
+	}
+	out.push(0);
+}
+
+/// Read an uncompressed name in the wire format, returning it in the dotted form and the rest of the bytes.
+/// The root name is returned as an empty name
+pub fn decode_name(bytes: &[u8]) -> rstd::result::Result<(Vec<u8>, &[u8]), &'static str> {
+	let mut name = Vec::new();
+	let mut rest = bytes;
+	loop {
+		let (len, tail) = rest.split_first().ok_or("The DNS record is truncated")?;
+		let len = *len as usize;
+		if len == 0 {
+			rest = tail;
+			break;
+		}
+		if len & 0xc0 != 0 {
+			return Err("Names in DNS records cannot be compressed");
+		}
+		if tail.len() < len {
+			return Err("The DNS record is truncated");
+		}
+		if !name.is_empty() {
+			name.push(b'.');
+		}
+		name.extend_from_slice(&tail[..len]);
+		rest = &tail[len..];
+	}
+	if !name.is_empty() {
+		name::validate_owner(&name)?;
+	}
+	Ok((name, rest))
+}
+
+fn read_u16(bytes: &[u8]) -> rstd::result::Result<(u16, &[u8]), &'static str> {
+	if bytes.len() < 2 {
+		return Err("The DNS record is truncated");
+	}
+	Ok((u16::from_be_bytes([bytes[0], bytes[1]]), &bytes[2..]))
+}
+
+fn ensure_end(rest: &[u8]) -> Result {
+	if rest.is_empty() {
+		Ok(())
+	} else {
+		Err("The DNS record has trailing bytes")
+	}
+}
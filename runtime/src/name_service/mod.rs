@@ -1,24 +1,38 @@
-use support::{decl_module, decl_storage, decl_event, dispatch::Result, ensure};
-use support::traits::{Currency, WithdrawReason, ExistenceRequirement};
-use system::{ensure_signed};
+use support::{decl_module, decl_storage, decl_event, dispatch::Result, ensure, StorageMap};
+use support::storage::unhashed;
+use support::traits::{Currency, ReservableCurrency, Get, OnUnbalanced, WithdrawReason, ExistenceRequirement};
+use sr_primitives::traits::{Hash, Zero, SimpleArithmetic};
+use sr_primitives::Perbill;
+use system::{ensure_signed, ensure_root};
 use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 use rstd::prelude::*;
+pub mod name;
+pub mod address;
+pub mod content_hash;
+pub mod dns;
+mod tests;
 
-// 1 year in blockseconds
-// each block is assumed to be generated in 6 seconds. divide that with 31556952(1 year) seconds and you get 5259492 blocks to represent 1 year in blockchain. 
-const YEAR: u32 =  5259492;
 pub type IPV4 = [u8; 4];
-pub type IPV6 = [u16; 6];
+pub type IPV6 = [u8; 16];
 pub type BYTES = Vec<u8>;
 
+/// Maximum number of domains returned by a page of the reverse resolution
+pub const MAX_PAGE_SIZE: u64 = 100;
+
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 pub struct Domain<AccountId, Balance, BlockNumber> {
-	/// domain name in bytestring e.g. b'hyungsukkang.eth'
+	/// normalized domain name in bytestring e.g. b'hyungsukkang.eth', at most `name::MAX_NAME_LENGTH` bytes
 	name: BYTES,
-	/// source of this domain a.k.a. the address of the blockchain
+	/// source of this domain a.k.a. the address of the blockchain. The registrant who can renew, transfer and sell it
 	source: AccountId,
+	/// account which manages the records of the domain, the source unless it is set with `set_controller`
+	controller: AccountId,
 	/// the current domain price
 	price: Balance,
+	/// deposit reserved from the source while it holds the domain
+	deposit: Balance,
 	/// Time to claim the ownership
 	ttl: BlockNumber,
 	/// Registered date in block height
@@ -29,14 +43,152 @@ pub struct Domain<AccountId, Balance, BlockNumber> {
 	highest_bid: Balance,
 	/// bidder who bidded highest
 	bidder: AccountId,
-	/// Auction closing date
+	/// second highest revealed bid in a sealed-bid auction, which is the price the highest bidder pays
+	second_bid: Balance,
+	/// Auction closing date. Sealed bids are revealed during the reveal period after this block
 	auction_closed: BlockNumber,
+	/// the kind of the current auction
+	auction_mode: AuctionMode,
+	// The IP addresses of the domain are kept in `IPV4Records` and `IPV6Records`.
+	// Domains stored before they moved there are in the `LegacyDomain` layout until they are migrated.
+}
 
-	/// TODO: Try to make browser engine which asks for this with Servo fork
-	/// IPV4 in case where the owner wants to put IP address
+/// Layout of the domains stored before the controller, the deposit and the auction modes were added
+/// and the IP addresses moved to `IPV4Records` and `IPV6Records`
+#[derive(Encode, Decode)]
+struct LegacyDomain<AccountId, Balance, BlockNumber> {
+	name: BYTES,
+	source: AccountId,
+	price: Balance,
+	ttl: BlockNumber,
+	registered_date: BlockNumber,
+	available: bool,
+	highest_bid: Balance,
+	bidder: AccountId,
+	auction_closed: BlockNumber,
 	ipv4: IPV4,
-	/// IPV6 in case where the owner wants to put IP address for his or her IoT device 
-	ipv6: IPV6,
+	ipv6: [u16; 6],
+}
+
+/// Lifecycle state of a domain
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum DomainState {
+	/// The domain is registered and not expired
+	Active,
+	/// The domain is expired but keeps resolving, and only its source can renew it
+	Grace,
+	/// Anyone can buy the domain with `buy_expired` at a decaying premium
+	Premium,
+	/// The domain is not registered or can be bought at its registration price
+	Available,
+}
+
+/// How the bids of an auction are made and settled
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum AuctionMode {
+	/// Public ascending bids with `new_bid`, the highest bidder pays its bid
+	English,
+	/// Bids are sealed with `seal_bid` while the auction is open and revealed with `reveal_bid` during the
+	/// reveal period. The highest bidder pays the second highest revealed bid(Vickrey auction)
+	SealedBid,
+	/// Public ascending bids with `new_bid`, but the auction is retroactively closed at a random block of the
+	/// ending period once it is over. The highest bidder at that block wins and pays its bid
+	Candle,
+}
+
+impl Default for AuctionMode {
+	fn default() -> Self {
+		AuctionMode::English
+	}
+}
+
+/// What a client needs to know about a domain, returned by the `NameServiceApi` of the runtime
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct DomainInfo<AccountId, Balance, BlockNumber, Hash> {
+	/// namehash of the domain
+	pub hash: Hash,
+	/// normalized domain name e.g. b"hyungsukkang.dot"
+	pub name: BYTES,
+	/// registrant of the domain
+	pub source: AccountId,
+	/// account which manages the records of the domain
+	pub controller: AccountId,
+	/// the current domain price
+	pub price: Balance,
+	/// deposit reserved from the source
+	pub deposit: Balance,
+	/// block at which the domain expires, the expiry of its parent for subdomains
+	pub expires_at: BlockNumber,
+	/// lifecycle state at the block the domain is queried at
+	pub state: DomainState,
+	/// whether the domain is in an auction
+	pub in_auction: bool,
+	/// A records, empty like the other records once the domain stops resolving
+	pub ipv4: Vec<IPV4>,
+	/// AAAA records
+	pub ipv6: Vec<IPV6>,
+	/// addresses on other chains as (SLIP-44 coin type, raw address) pairs
+	pub addresses: Vec<(u32, BYTES)>,
+	/// EIP-1577 content hash
+	pub content_hash: Option<BYTES>,
+}
+
+/// State of the auction of a domain, returned by the `NameServiceApi` of the runtime
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AuctionStatus<AccountId, Balance, BlockNumber> {
+	pub mode: AuctionMode,
+	/// highest bid so far, sealed bids only count once they are revealed
+	pub highest_bid: Balance,
+	/// highest bidder so far, the source while there are no bids
+	pub bidder: AccountId,
+	/// second highest revealed bid of a sealed-bid auction
+	pub second_bid: Balance,
+	/// last block at which bids are taken
+	pub closes_at: BlockNumber,
+	/// block from which the auction can be finalized, after the reveal period of a sealed-bid auction
+	pub ends_at: BlockNumber,
+	/// first block of the ending period of a candle auction
+	pub ending_start: Option<BlockNumber>,
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct SealedBid<Hash, Balance> {
+	/// hash of the SCALE encoded tuple (bid, salt)
+	sealed: Hash,
+	/// deposit reserved with the sealed bid, which has to cover the bid
+	deposit: Balance,
+	/// whether the bid has been revealed
+	revealed: bool,
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RecordSet<AccountId, Balance> {
+	/// DNS TTL of the records in seconds, unrelated to the `ttl` of the domain
+	pub ttl: u32,
+	/// RDATA of the records in the wire format, see `dns`
+	pub records: Vec<BYTES>,
+	/// account which set the records and has the deposit reserved
+	pub depositor: AccountId,
+	/// deposit for the bytes of the records
+	pub deposit: Balance,
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TextRecord<AccountId, Balance> {
+	/// value of the record e.g. b"hyungsukkang@example.com" for the `email` key
+	pub value: BYTES,
+	/// account which set the record and has the deposit reserved
+	pub depositor: AccountId,
+	/// deposit for the bytes of the key and the value
+	pub deposit: Balance,
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
@@ -49,28 +201,98 @@ pub struct DataPoint<AccountId> {
 	data: BYTES,
 }
 
+/// Pricing policy of domain registrations and renewals
+pub trait PriceOracle<Balance> {
+	/// Price of the domain with the name for a registration period
+	fn price(domain_name: &[u8]) -> Balance;
+}
+
+/// Default pricing policy. Premium names set by root have their own price, and the other names
+/// are priced by the length of their first label so that short names are expensive to squat:
+/// 1000 times the base price for up to 3 characters, 100 times for 4 characters and the base price for the rest
+pub struct LengthPricing<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PriceOracle<T::Balance> for LengthPricing<T> {
+	fn price(domain_name: &[u8]) -> T::Balance {
+		if let Some(price) = <Module<T>>::premium_price(<Module<T>>::domain_hash_of(domain_name)) {
+			return price;
+		}
+		let base_price = T::BasePrice::get();
+		match name::label_length(domain_name) {
+			0..=3 => base_price * T::Balance::from(1000),
+			4 => base_price * T::Balance::from(100),
+			_ => base_price,
+		}
+	}
+}
+
+/// Curve of the premium an expired domain is sold at on top of its registration price
+pub trait PremiumDecay<Balance, BlockNumber> {
+	/// Premium `elapsed` blocks into a premium period of `period` blocks which starts at the `start` premium
+	fn premium(start: Balance, elapsed: BlockNumber, period: BlockNumber) -> Balance;
+}
+
+/// The premium decays linearly to zero at the end of the premium period
+pub struct LinearDecay;
+
+impl<Balance: SimpleArithmetic + Copy, BlockNumber: SimpleArithmetic + Copy> PremiumDecay<Balance, BlockNumber> for LinearDecay {
+	fn premium(start: Balance, elapsed: BlockNumber, period: BlockNumber) -> Balance {
+		if elapsed >= period {
+			return Zero::zero();
+		}
+		Perbill::from_rational_approximation(period - elapsed, period) * start
+	}
+}
+
+/// The premium halves every `HalfLife` blocks and drops to zero at the end of the premium period
+pub struct ExponentialDecay<HalfLife>(rstd::marker::PhantomData<HalfLife>);
+
+impl<Balance, BlockNumber, HalfLife> PremiumDecay<Balance, BlockNumber> for ExponentialDecay<HalfLife> where
+	Balance: SimpleArithmetic + Copy,
+	BlockNumber: SimpleArithmetic + Copy,
+	HalfLife: Get<BlockNumber>,
+{
+	fn premium(start: Balance, elapsed: BlockNumber, period: BlockNumber) -> Balance {
+		let half_life = HalfLife::get();
+		if elapsed >= period || half_life.is_zero() {
+			return Zero::zero();
+		}
+		let mut premium = start;
+		let mut halvings = elapsed / half_life;
+		while !halvings.is_zero() && !premium.is_zero() {
+			premium = premium / Balance::from(2);
+			halvings -= BlockNumber::from(1);
+		}
+		premium
+	}
+}
+
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
 // Module's function and Methods of custom struct to be placed here
 impl<T: Trait> Module<T> {
 
 	pub fn new_domain(domain_name: BYTES, source: T::AccountId) -> Domain<T::AccountId, T::Balance, T::BlockNumber> {
 		// Convert numbers into generic types which is mapped to native type in lib.rs
 		// Generic types can process arithmetics and comparisons just as other rust variables
-		let ttl = T::BlockNumber::from(YEAR);
-		let init_price = Self::to_balance(1, "milli");
+		let ttl = T::RegistrationPeriod::get();
+		let init_price = T::PriceOracle::price(&domain_name);
 		let reg_date: T::BlockNumber = <system::Module<T>>::block_number(); 
 		
 		Domain {
 			name: domain_name,
 			source: source.clone(),
+			controller: source.clone(),
 			price: init_price,
+			deposit: T::Balance::from(0),
 			ttl: ttl,
 			registered_date: reg_date,
 			available: false,
 			highest_bid: T::Balance::from(0),
 			bidder: source,
+			second_bid: T::Balance::from(0),
 			auction_closed: T::BlockNumber::from(0),
-			ipv4: [0,0,0,0],
-			ipv6: [0,0,0,0,0,0],
+			auction_mode: AuctionMode::English,
 		}
 	}
 
@@ -104,16 +326,656 @@ impl<T: Trait> Module<T> {
 		result 
 	}
 
-	pub fn remove_domain(domain_hash: T::Hash, domains: Vec<T::Hash>) -> Vec<T::Hash> {
-		let mut new_reverse_list: Vec<T::Hash> = vec!{};
+	/// Commitment for the registration of `domain_name` by `owner`.
+	/// Off-chain, this is the hash of the SCALE encoded tuple `(domain_name, salt, owner)`.
+	pub fn commitment_of(domain_name: &BYTES, salt: &T::Hash, owner: &T::AccountId) -> T::Hash {
+		T::Hashing::hash_of(&(domain_name, salt, owner))
+	}
+
+	/// Key of the domain in the Resolver state, which is the namehash of the name(see `name::namehash`).
+	/// The key is always computed on chain from the name so that the name stored in a domain is the one its key represents.
+	pub fn domain_hash_of(domain_name: &[u8]) -> T::Hash {
+		name::namehash::<T::Hashing>(domain_name)
+	}
+
+	/// Register a second level domain e.g. company.dot for the owner after its commitment has been revealed
+	fn register(owner: T::AccountId, domain_name: BYTES) -> Result {
+		name::validate(&domain_name)?;
+		let domain_hash = Self::domain_hash_of(&domain_name);
+		ensure!(!<Resolver<T>>::exists(domain_hash), "The domain already exists");
+		// Names under a domain belong to the owner of that domain, who creates them with `create_subdomain`
+		let parent = name::parent(&domain_name).ok_or("Top level domains cannot be registered")?;
+		ensure!(name::parent(parent).is_none(), "Subdomains can only be created by the source of the parent domain");
+		// Make new Domain struct
+		let mut new_domain = Self::new_domain(domain_name, owner.clone());
+
+		// Reserve the registration price as the deposit of the domain, which is returned when the domain is released
+		T::Currency::reserve(&owner, new_domain.price)?;
+		new_domain.deposit = new_domain.price;
+
+		Self::add_reverse(owner.clone(), domain_hash);
+
+		// Insert new domain to the Resolver state
+		<Resolver<T>>::insert(domain_hash, new_domain.clone());
+
+		// Increment domain number
+		let mut domains = Self::total_domains();
+		domains = domains.wrapping_add(1);
+
+		// Store domain number to Domains state
+		Domains::put(domains);
+
+		// Deposit event
+		Self::deposit_event(RawEvent::DomainRegistered(owner, new_domain.price, new_domain.ttl, new_domain.registered_date));
+
+		Ok(())
+	}
+
+	/// Remove the commitments which became too old to be revealed at this block
+	fn expire_commitments(now: T::BlockNumber) {
+		let max_age = T::MaxCommitmentAge::get();
+		for commitment in <CommitmentExpiry<T>>::take(now) {
+			// The same commitment might have been revealed and made again after this entry was queued
+			if let Some((_, committed)) = Self::commitment(commitment) {
+				if committed + max_age < now {
+					<Commitments<T>>::remove(commitment);
+				}
+			}
+		}
+	}
+
+	/// Block at which the domain expires. A subdomain never outlives its parent domain
+	pub fn expires_at(domain_hash: T::Hash) -> T::BlockNumber {
+		let domain = Self::domain(domain_hash);
+		let expiry = domain.registered_date + domain.ttl;
+		match Self::parent(domain_hash) {
+			Some(parent_hash) => rstd::cmp::min(expiry, Self::expires_at(parent_hash)),
+			None => expiry,
+		}
+	}
+
+	/// Add the domain to the end of the owner index of the account
+	fn add_reverse(account: T::AccountId, domain_hash: T::Hash) {
+		let index = Self::owned_count(account.clone());
+		<OwnedDomains<T>>::insert(&account, &index, domain_hash);
+		<OwnedIndex<T>>::insert(&account, &domain_hash, index);
+		<OwnedCount<T>>::insert(account, index + 1);
+	}
+	/// Remove the domain from the owner index of the account by moving its last domain in its place,
+	/// and unset it as the primary name of the account
+	fn remove_reverse(account: T::AccountId, domain_hash: T::Hash) {
+		if let Some(index) = <OwnedIndex<T>>::take(&account, &domain_hash) {
+			let last = Self::owned_count(account.clone()) - 1;
+			if let Some(last_hash) = <OwnedDomains<T>>::take(&account, &last) {
+				if index != last {
+					<OwnedDomains<T>>::insert(&account, &index, last_hash);
+					<OwnedIndex<T>>::insert(&account, &last_hash, index);
+				}
+			}
+			<OwnedCount<T>>::insert(account.clone(), last);
+		}
+		if Self::primary(account.clone()) == Some(domain_hash) {
+			<PrimaryNames<T>>::remove(account.clone());
+			Self::deposit_event(RawEvent::PrimaryNameCleared(account));
+		}
+	}
+	/// What a client needs to know about the domain, if it is registered.
+	/// The records are left out once the domain stops resolving
+	pub fn domain_info(domain_hash: T::Hash) -> Option<DomainInfo<T::AccountId, T::Balance, T::BlockNumber, T::Hash>> {
+		if !<Resolver<T>>::exists(domain_hash) {
+			return None;
+		}
+		let domain = Self::domain(domain_hash);
+		let resolves = Self::resolves(domain_hash);
+		Some(DomainInfo {
+			hash: domain_hash,
+			expires_at: Self::expires_at(domain_hash),
+			state: Self::domain_state(domain_hash),
+			in_auction: domain.available,
+			ipv4: if resolves { Self::ipv4(domain_hash) } else { Vec::new() },
+			ipv6: if resolves { Self::ipv6(domain_hash) } else { Vec::new() },
+			addresses: Self::coin_types(domain_hash).into_iter()
+				.filter_map(|coin_type| Self::resolve_address(domain_hash, coin_type).map(|address| (coin_type, address)))
+				.collect(),
+			content_hash: Self::resolve_content_hash(domain_hash),
+			name: domain.name,
+			source: domain.source,
+			controller: domain.controller,
+			price: domain.price,
+			deposit: domain.deposit,
+		})
+	}
+	/// State of the auction of the domain, if it is in one
+	pub fn auction_status(domain_hash: T::Hash) -> Option<AuctionStatus<T::AccountId, T::Balance, T::BlockNumber>> {
+		let domain = Self::domain(domain_hash);
+		if !<Resolver<T>>::exists(domain_hash) || !domain.available {
+			return None;
+		}
+		Some(AuctionStatus {
+			mode: domain.auction_mode,
+			closes_at: domain.auction_closed,
+			ends_at: Self::auction_end(&domain),
+			ending_start: if domain.auction_mode == AuctionMode::Candle { Some(Self::ending_start(&domain)) } else { None },
+			highest_bid: domain.highest_bid,
+			bidder: domain.bidder,
+			second_bid: domain.second_bid,
+		})
+	}
+	/// Domains owned by the account from the index `start` on, at most `limit` of them and `MAX_PAGE_SIZE`
+	pub fn domains_of(account: T::AccountId, start: u64, limit: u64) -> Vec<T::Hash> {
+		let end = Self::owned_count(account.clone()).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+		(start..end).filter_map(|index| Self::owned_domain(&account, &index)).collect()
+	}
+	/// Put the domain into the owner index of its source, dropping the legacy `Reverse` list of the source.
+	/// Returns whether the domain was missing from the index
+	fn index_domain(domain_hash: T::Hash) -> bool {
+		if !<Resolver<T>>::exists(domain_hash) {
+			return false;
+		}
+		let source = Self::domain(domain_hash).source;
+		if <OwnedIndex<T>>::exists(&source, &domain_hash) {
+			return false;
+		}
+		<Reverse<T>>::remove(source.clone());
+		Self::add_reverse(source, domain_hash);
+		true
+	}
+	/// Primary name of the account, as long as the domain still resolves to the account as its source or controller
+	pub fn primary_name(account: T::AccountId) -> Option<T::Hash> {
+		let domain_hash = Self::primary(account.clone())?;
+		if !Self::resolves(domain_hash) {
+			return None;
+		}
+		let domain = Self::domain(domain_hash);
+		if domain.source == account || domain.controller == account {
+			Some(domain_hash)
+		} else {
+			None
+		}
+	}
+	/// Whether the records of the domain resolve, which they keep doing in the grace period
+	fn resolves(domain_hash: T::Hash) -> bool {
+		match Self::domain_state(domain_hash) {
+			DomainState::Active | DomainState::Grace => <Resolver<T>>::exists(domain_hash),
+			_ => false,
+		}
+	}
+
+	/// Deposit to be reserved for a domain while it is held, which is its current registration price
+	fn registration_deposit(domain_name: &[u8]) -> T::Balance {
+		T::PriceOracle::price(domain_name)
+	}
+
+	/// Whether the account can manage the records of the domain, as its controller or an operator approved by its source
+	pub fn is_manager(domain_hash: T::Hash, domain: &Domain<T::AccountId, T::Balance, T::BlockNumber>, who: &T::AccountId) -> bool {
+		*who == domain.controller
+			|| Self::operator_approval(&domain.source, who)
+			|| Self::domain_approval(&domain.source, &(domain_hash, who.clone()))
+	}
+	/// Deposit for storing a text record with the key and the value
+	fn text_deposit(key: &[u8], value: &[u8]) -> T::Balance {
+		T::TextByteDeposit::get() * T::Balance::from((key.len() + value.len()) as u32)
+	}
+	/// Remove the text record and return its deposit
+	fn remove_text(domain_hash: T::Hash, key: &BYTES) {
+		if let Some(record) = <TextRecords<T>>::take(&domain_hash, key) {
+			T::Currency::unreserve(&record.depositor, record.deposit);
+		}
+	}
+	/// Remove all records of the domain
+	fn clear_records(domain_hash: T::Hash) {
+		for key in <TextKeys<T>>::take(domain_hash) {
+			Self::remove_text(domain_hash, &key);
+		}
+		<IPV4Records<T>>::remove(domain_hash);
+		<IPV6Records<T>>::remove(domain_hash);
+		for coin_type in <CoinTypes<T>>::take(domain_hash) {
+			<AddressRecords<T>>::remove(&domain_hash, &coin_type);
+		}
+		<ContentHashes<T>>::remove(domain_hash);
+		for rtype in <DnsRecordTypes<T>>::take(domain_hash) {
+			Self::remove_dns_records(domain_hash, rtype);
+		}
+		<AddressTtls<T>>::remove(&domain_hash, &dns::TYPE_A);
+		<AddressTtls<T>>::remove(&domain_hash, &dns::TYPE_AAAA);
+	}
+	/// Remove the DNS record set of the type and return its deposit
+	fn remove_dns_records(domain_hash: T::Hash, rtype: u16) {
+		if let Some(set) = <DnsRecords<T>>::take(&domain_hash, &rtype) {
+			T::Currency::unreserve(&set.depositor, set.deposit);
+		}
+	}
+	/// Ensure that pointing the domain at the target does not make a loop of CNAMEs
+	fn ensure_no_cname_cycle(domain_hash: T::Hash, target: &[u8]) -> Result {
+		let mut target = target.to_vec();
+		for _ in 0..dns::MAX_CNAME_CHAIN {
+			let target_hash = Self::domain_hash_of(&target);
+			if target_hash == domain_hash {
+				return Err("The CNAME would make a cycle");
+			}
+			let set = match Self::dns_records(&target_hash, &dns::TYPE_CNAME) {
+				Some(set) => set,
+				None => return Ok(()),
+			};
+			target = match set.records.first().map(|rdata| dns::RecordData::from_wire(dns::TYPE_CNAME, rdata)) {
+				Some(Ok(dns::RecordData::Cname(next))) => next,
+				_ => return Ok(()),
+			};
+		}
+		Err("The CNAME chain is too long")
+	}
+	/// DNS TTL and RDATA of the records of the type, while the domain resolves. A and AAAA records come
+	/// from the IP addresses, and TXT records from the text records in the `key=value` form of RFC 1464
+	/// unless the domain has TXT records of its own
+	pub fn resolve_dns(domain_hash: T::Hash, rtype: u16) -> Option<(u32, Vec<BYTES>)> {
+		if !Self::resolves(domain_hash) {
+			return None;
+		}
+		let address_ttl = || Self::address_ttl(&domain_hash, &rtype).unwrap_or(dns::DEFAULT_TTL);
+		let records: Vec<BYTES> = match rtype {
+			dns::TYPE_A => Self::ipv4(domain_hash).iter().map(|ip| ip.to_vec()).collect(),
+			dns::TYPE_AAAA => Self::ipv6(domain_hash).iter().map(|ip| ip.to_vec()).collect(),
+			dns::TYPE_TXT if !<DnsRecords<T>>::exists(&domain_hash, &rtype) => {
+				let records: Vec<BYTES> = Self::resolve_texts(domain_hash).into_iter().map(|(mut text, value)| {
+					text.push(b'=');
+					text.extend(value);
+					dns::RecordData::Txt(text.chunks(255).map(|chunk| chunk.to_vec()).collect()).to_wire()
+				}).collect();
+				return if records.is_empty() { None } else { Some((dns::DEFAULT_TTL, records)) };
+			}
+			_ => return Self::dns_records(&domain_hash, &rtype).map(|set| (set.ttl, set.records)),
+		};
+		if records.is_empty() { None } else { Some((address_ttl(), records)) }
+	}
+	/// Text records of the domain as (key, value) pairs, while the domain resolves
+	pub fn resolve_texts(domain_hash: T::Hash) -> Vec<(BYTES, BYTES)> {
+		if !Self::resolves(domain_hash) {
+			return Vec::new();
+		}
+		Self::text_keys(domain_hash).into_iter()
+			.filter_map(|key| Self::text(&domain_hash, &key).map(|record| (key, record.value)))
+			.collect()
+	}
+	/// Content hash of the domain, while the domain resolves
+	pub fn resolve_content_hash(domain_hash: T::Hash) -> Option<BYTES> {
+		if Self::resolves(domain_hash) { Self::content_hash(domain_hash) } else { None }
+	}
+	/// Address of the domain on the chain with the coin type, while the domain resolves
+	pub fn resolve_address(domain_hash: T::Hash, coin_type: u32) -> Option<BYTES> {
+		if Self::resolves(domain_hash) { Self::address(&domain_hash, &coin_type) } else { None }
+	}
+	/// Domain stored under the namehash. A domain still stored in the legacy layout is migrated the first time
+	/// it is read, so that no later write of the domain drops its legacy IPV4 address
+	pub fn domain(domain_hash: T::Hash) -> Domain<T::AccountId, T::Balance, T::BlockNumber> {
+		Self::load_domain(domain_hash).map(|(domain, _)| domain).unwrap_or_default()
+	}
+	/// Domain stored under the namehash, and whether it was just migrated from the `LegacyDomain` layout.
+	/// The IPV4 address of a legacy domain moves to `IPV4Records`, its IPV6 field is dropped as it could never be set,
+	/// and its auction is closed
+	fn load_domain(domain_hash: T::Hash) -> Option<(Domain<T::AccountId, T::Balance, T::BlockNumber>, bool)> {
+		let raw = unhashed::get_raw(&<Resolver<T>>::hashed_key_for(domain_hash))?;
+		if let Ok(domain) = Domain::decode(&mut &raw[..]) {
+			return Some((domain, false));
+		}
+		let input = &mut &raw[..];
+		let legacy = LegacyDomain::<T::AccountId, T::Balance, T::BlockNumber>::decode(input).ok()?;
+		if !input.is_empty() {
+			return None;
+		}
+
+		// Legacy domains burned their registration fee, so they have no deposit to return
+		let mut domain = Domain {
+			name: legacy.name,
+			source: legacy.source.clone(),
+			controller: legacy.source,
+			price: legacy.price,
+			deposit: Zero::zero(),
+			ttl: legacy.ttl,
+			registered_date: legacy.registered_date,
+			available: legacy.available,
+			highest_bid: legacy.highest_bid,
+			bidder: legacy.bidder,
+			second_bid: Zero::zero(),
+			auction_closed: legacy.auction_closed,
+			auction_mode: AuctionMode::English,
+		};
+		// Legacy bids were never escrowed, so an open legacy auction is closed instead of
+		// settling or refunding reserves which the bidder holds for something else
+		Self::close_auction(&mut domain);
+		if legacy.ipv4 != [0u8; 4] && !<IPV4Records<T>>::get(domain_hash).contains(&legacy.ipv4) {
+			<IPV4Records<T>>::mutate(domain_hash, |addresses| addresses.push(legacy.ipv4));
+		}
+		<Resolver<T>>::insert(domain_hash, domain.clone());
+		Some((domain, true))
+	}
+	/// Set the IPV4 addresses of the domain after checking that the sender manages it
+	fn update_ipv4(sender: &T::AccountId, domain_hash: T::Hash, f: impl FnOnce(&mut Vec<IPV4>) -> Result) -> Result {
+		ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+		ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), sender), "You are not the controller or an operator of the domain");
+		ensure!(!<DnsRecords<T>>::exists(&domain_hash, &dns::TYPE_CNAME), "The domain is an alias with a CNAME record");
+
+		let mut addresses = Self::ipv4(domain_hash);
+		f(&mut addresses)?;
+		ensure!(addresses.len() <= T::MaxAddresses::get() as usize, "The domain has too many addresses");
+		<IPV4Records<T>>::insert(domain_hash, addresses.clone());
+		Self::deposit_event(RawEvent::SetIPV4(domain_hash, addresses));
+
+		Ok(())
+	}
+	/// Set the IPV6 addresses of the domain after checking that the sender manages it
+	fn update_ipv6(sender: &T::AccountId, domain_hash: T::Hash, f: impl FnOnce(&mut Vec<IPV6>) -> Result) -> Result {
+		ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+		ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), sender), "You are not the controller or an operator of the domain");
+		ensure!(!<DnsRecords<T>>::exists(&domain_hash, &dns::TYPE_CNAME), "The domain is an alias with a CNAME record");
+
+		let mut addresses = Self::ipv6(domain_hash);
+		f(&mut addresses)?;
+		ensure!(addresses.len() <= T::MaxAddresses::get() as usize, "The domain has too many addresses");
+		<IPV6Records<T>>::insert(domain_hash, addresses.clone());
+		Self::deposit_event(RawEvent::SetIPV6(domain_hash, addresses));
+
+		Ok(())
+	}
+	/// Remove the proposed transfers which can no longer be accepted at this block
+	fn expire_transfers(now: T::BlockNumber) {
+		for domain_hash in <TransferExpiry<T>>::take(now) {
+			// The transfer might have been proposed again after this entry was queued
+			if let Some((_, _, expiry)) = Self::pending_transfer(domain_hash) {
+				if expiry <= now {
+					<PendingTransfers<T>>::remove(domain_hash);
+				}
+			}
+		}
+	}
+	/// Remove the domain with its subdomains from the state and return the deposit to its source
+	fn release(domain_hash: T::Hash) {
+		Self::revoke_subdomains(domain_hash);
+		Self::clear_records(domain_hash);
+		<PendingTransfers<T>>::remove(domain_hash);
+
+		let domain = Self::domain(domain_hash);
+		Self::remove_reverse(domain.source.clone(), domain_hash);
+		if let Some(parent_hash) = <Parent<T>>::take(domain_hash) {
+			<Children<T>>::mutate(parent_hash, |children| children.retain(|child| *child != domain_hash));
+		}
+		<Resolver<T>>::remove(domain_hash);
+		Domains::mutate(|domains| *domains = domains.wrapping_sub(1));
+
+		T::Currency::unreserve(&domain.source, domain.deposit);
+		Self::deposit_event(RawEvent::DomainReleased(domain_hash, domain.source, domain.deposit));
+	}
+
+	/// Block after which the auction of the domain can be finalized
+	fn auction_end(domain: &Domain<T::AccountId, T::Balance, T::BlockNumber>) -> T::BlockNumber {
+		match domain.auction_mode {
+			AuctionMode::English => domain.auction_closed,
+			AuctionMode::SealedBid => domain.auction_closed + T::RevealPeriod::get(),
+			AuctionMode::Candle => domain.auction_closed,
+		}
+	}
+
+	/// First block of the ending period of a candle auction, in which the auction might be closed
+	fn ending_start(domain: &Domain<T::AccountId, T::Balance, T::BlockNumber>) -> T::BlockNumber {
+		let ending_period = rstd::cmp::min(T::EndingPeriod::get(), T::AuctionPeriod::get());
+		domain.auction_closed - ending_period
+	}
+
+	/// Draw the block of the ending period at which the candle auction of the domain closes, once its bids are over.
+	/// The randomness of the block after the last bid is used so that nobody can pick it by choosing when to finalize
+	fn draw_candle_closing(domain_hash: T::Hash) {
+		let domain = Self::domain(domain_hash);
+		if !domain.available || domain.auction_mode != AuctionMode::Candle || <CandleClosing<T>>::exists(domain_hash) {
+			return;
+		}
+		let ending_start = Self::ending_start(&domain);
+		let ending_period = domain.auction_closed - ending_start;
+		let random = (<system::Module<T>>::random_seed(), domain_hash).using_encoded(T::Hashing::hash);
+		let offset = u32::decode(&mut random.as_ref()).unwrap_or_default();
+		let closing = if ending_period.is_zero() {
+			ending_start
+		} else {
+			ending_start + T::BlockNumber::from(offset) % ending_period
+		};
+		<CandleClosing<T>>::insert(domain_hash, closing);
+	}
+
+	/// Draw the closing blocks of the candle auctions whose bids are over at this block
+	fn draw_candle_closings(now: T::BlockNumber) {
+		for domain_hash in <CandleDraws<T>>::take(now) {
+			Self::draw_candle_closing(domain_hash);
+		}
+	}
 
-		for i in domains {
-			if i != domain_hash {
-				new_reverse_list.push(i);
+	/// Settle a candle auction. The highest bidder at the closing block drawn by `draw_candle_closing` pays its bid,
+	/// and every other escrowed bid is refunded
+	fn settle_candle(domain_hash: T::Hash, domain: Domain<T::AccountId, T::Balance, T::BlockNumber>) -> Result {
+		let ending_start = Self::ending_start(&domain);
+		let closing = Self::candle_closing(domain_hash).ok_or("The closing block of the candle auction is not drawn yet")?;
+
+		// The winning bid is the latest one made at or before the closing block
+		let mut winner = None;
+		let mut block = closing;
+		loop {
+			if let Some(bid) = Self::candle_bid(&domain_hash, &block) {
+				winner = Some(bid);
+				break;
+			}
+			if block <= ending_start {
+				break;
 			}
+			block -= T::BlockNumber::from(1);
+		}
+
+		// Everything which can fail is done before the bids are cleared, so that a failed payment leaves the auction untouched
+		let deposit = Self::registration_deposit(&domain.name);
+		let winner = winner.filter(|(bidder, _)| T::Currency::can_reserve(bidder, deposit));
+		if let Some((ref buyer, price)) = winner {
+			T::Currency::repatriate_reserved(buyer, &domain.source, price)?;
+		}
+
+		<CandleClosing<T>>::remove(domain_hash);
+		let mut block = ending_start;
+		while block <= domain.auction_closed {
+			<CandleBids<T>>::remove(&domain_hash, &block);
+			block += T::BlockNumber::from(1);
+		}
+		for bidder in <CandleBidders<T>>::take(domain_hash) {
+			let mut refund = <CandleEscrow<T>>::take(&domain_hash, &bidder);
+			if let Some((ref buyer, price)) = winner {
+				if *buyer == bidder {
+					refund -= price;
+				}
+			}
+			T::Currency::unreserve(&bidder, refund);
+		}
+
+		match winner {
+			Some((buyer, price)) => {
+				Self::deposit_event(RawEvent::CandleClosed(domain_hash, closing));
+				Self::finalize_sale(domain_hash, domain, buyer, price, deposit)
+			},
+			None => Self::fail_auction(domain_hash, domain),
+		}
+	}
+
+	/// Settle an English auction where the highest bidder pays its escrowed bid
+	fn settle_english(domain_hash: T::Hash, domain: Domain<T::AccountId, T::Balance, T::BlockNumber>) -> Result {
+		// Everything which can fail is checked before the state is changed, so that a failed sale leaves the domain untouched
+		let deposit = Self::registration_deposit(&domain.name);
+		let sold = domain.bidder != domain.source
+			&& T::Currency::reserved_balance(&domain.bidder) >= domain.highest_bid
+			&& T::Currency::can_reserve(&domain.bidder, deposit);
+		if !sold {
+			// Nobody bid or the bidder cannot afford the deposit of the domain.
+			// The domain stays with its source and the escrowed bid is refunded
+			T::Currency::unreserve(&domain.bidder, domain.highest_bid);
+			return Self::fail_auction(domain_hash, domain);
 		}
 
-		return new_reverse_list;
+		// Pay the source with the escrowed bid
+		T::Currency::repatriate_reserved(&domain.bidder, &domain.source, domain.highest_bid)?;
+		let (buyer, price) = (domain.bidder.clone(), domain.highest_bid);
+		Self::finalize_sale(domain_hash, domain, buyer, price, deposit)
+	}
+
+	/// Settle a sealed-bid auction where the highest bidder pays the second highest revealed bid.
+	/// Every sealed deposit is returned, except for a part of the deposits of the bids which were not revealed
+	fn settle_sealed_bids(domain_hash: T::Hash, domain: Domain<T::AccountId, T::Balance, T::BlockNumber>) -> Result {
+		let deposit = Self::registration_deposit(&domain.name);
+		// The registration price of the domain is the reserve price of the auction, so a lower highest bid does not sell
+		let price = rstd::cmp::max(domain.second_bid, deposit);
+		let sold = domain.bidder != domain.source
+			&& domain.highest_bid >= deposit
+			&& T::Currency::can_reserve(&domain.bidder, deposit);
+
+		// Pay the source before the bids are cleared, so that a failed payment leaves the auction untouched
+		if sold {
+			T::Currency::repatriate_reserved(&domain.bidder, &domain.source, price)?;
+		}
+		for bidder in <SealedBidders<T>>::take(domain_hash) {
+			let sealed_bid = match <SealedBids<T>>::take(&domain_hash, &bidder) {
+				Some(sealed_bid) => sealed_bid,
+				None => continue,
+			};
+			let mut refund = sealed_bid.deposit;
+			if !sealed_bid.revealed {
+				let penalty = T::UnrevealedBidPenalty::get() * sealed_bid.deposit;
+				let _ = T::Currency::slash_reserved(&bidder, penalty);
+				refund -= penalty;
+			} else if sold && bidder == domain.bidder {
+				refund -= price;
+			}
+			T::Currency::unreserve(&bidder, refund);
+		}
+
+		if !sold {
+			return Self::fail_auction(domain_hash, domain);
+		}
+		let buyer = domain.bidder.clone();
+		Self::finalize_sale(domain_hash, domain, buyer, price, deposit)
+	}
+
+	/// Close the auction without a sale, leaving the domain with its source
+	fn fail_auction(domain_hash: T::Hash, mut domain: Domain<T::AccountId, T::Balance, T::BlockNumber>) -> Result {
+		Self::close_auction(&mut domain);
+		<Resolver<T>>::insert(domain_hash, domain.clone());
+		Self::deposit_event(RawEvent::AuctionFailed(domain_hash, domain.source));
+
+		Ok(())
+	}
+
+	/// Hand the domain over to the winner of its auction
+	fn finalize_sale(domain_hash: T::Hash, domain: Domain<T::AccountId, T::Balance, T::BlockNumber>, buyer: T::AccountId, price: T::Balance, deposit: T::Balance) -> Result {
+		Self::change_owner(domain_hash, domain, buyer.clone(), price, deposit)?;
+		Self::deposit_event(RawEvent::AuctionFinalized(buyer, domain_hash, price));
+
+		Ok(())
+	}
+
+	/// Hand the domain over to the buyer, who reserves the deposit of the domain from now on
+	fn change_owner(domain_hash: T::Hash, mut domain: Domain<T::AccountId, T::Balance, T::BlockNumber>, buyer: T::AccountId, price: T::Balance, deposit: T::Balance) -> Result {
+		T::Currency::unreserve(&domain.source, domain.deposit);
+		T::Currency::reserve(&buyer, deposit)?;
+		domain.deposit = deposit;
+
+		// Remove domain hash from the prior owner's reverse registrar
+		Self::remove_reverse(domain.source.clone(), domain_hash);
+
+		// Set reverse for the new owner
+		Self::add_reverse(buyer.clone(), domain_hash);
+
+		// The subdomains, the records and the proposed transfer were given out by the prior owner
+		Self::revoke_subdomains(domain_hash);
+		Self::clear_records(domain_hash);
+		<PendingTransfers<T>>::remove(domain_hash);
+
+		// Set new domain data to buyer as source, the price paid as price, and reinitialize rest of them
+		domain.source = buyer.clone();
+		domain.controller = buyer.clone();
+		domain.price = price;
+		domain.ttl = T::RegistrationPeriod::get();
+		domain.registered_date = <system::Module<T>>::block_number();
+		Self::close_auction(&mut domain);
+
+		<Resolver<T>>::insert(domain_hash, domain);
+
+		Ok(())
+	}
+
+	/// Hand the domain over to the new owner as it is. The deposit is returned to the prior owner and reserved from the new one
+	fn transfer(domain_hash: T::Hash, mut domain: Domain<T::AccountId, T::Balance, T::BlockNumber>, new_owner: T::AccountId) -> Result {
+		ensure!(T::Currency::can_reserve(&new_owner, domain.deposit), "The new owner cannot afford the deposit of the domain");
+		let old_owner = domain.source.clone();
+		T::Currency::unreserve(&old_owner, domain.deposit);
+		T::Currency::reserve(&new_owner, domain.deposit)?;
+
+		Self::remove_reverse(old_owner.clone(), domain_hash);
+		Self::add_reverse(new_owner.clone(), domain_hash);
+
+		domain.source = new_owner.clone();
+		domain.controller = new_owner.clone();
+		<Resolver<T>>::insert(domain_hash, domain);
+		<PendingTransfers<T>>::remove(domain_hash);
+		Self::deposit_event(RawEvent::DomainTransferred(domain_hash, old_owner, new_owner));
+
+		Ok(())
+	}
+
+	/// Lifecycle state of the domain at the current block
+	pub fn domain_state(domain_hash: T::Hash) -> DomainState {
+		if !<Resolver<T>>::exists(domain_hash) {
+			return DomainState::Available;
+		}
+		let now = <system::Module<T>>::block_number();
+		let expiry = Self::expires_at(domain_hash);
+		let premium_start = expiry + T::GracePeriod::get();
+		if now < expiry {
+			DomainState::Active
+		} else if now < premium_start {
+			DomainState::Grace
+		} else if now < premium_start + T::PremiumPeriod::get() {
+			DomainState::Premium
+		} else {
+			DomainState::Available
+		}
+	}
+
+	/// Premium on top of the registration price at which the expired domain is sold after its grace period
+	pub fn premium(domain_hash: T::Hash) -> T::Balance {
+		let now = <system::Module<T>>::block_number();
+		let premium_start = Self::expires_at(domain_hash) + T::GracePeriod::get();
+		if now < premium_start {
+			return Zero::zero();
+		}
+		T::PremiumDecay::premium(T::PremiumStartPrice::get(), now - premium_start, T::PremiumPeriod::get())
+	}
+
+	/// Reset the auction data of the domain once its auction is over
+	fn close_auction(domain: &mut Domain<T::AccountId, T::Balance, T::BlockNumber>) {
+		domain.available = false;
+		domain.bidder = domain.source.clone();
+		domain.highest_bid = T::Balance::from(0);
+		domain.second_bid = T::Balance::from(0);
+		domain.auction_closed = T::BlockNumber::from(0);
+		domain.auction_mode = AuctionMode::English;
+	}
+
+	/// Remove all subdomains under the domain, e.g. when the domain changes hands.
+	/// The new source of the domain starts over with no subdomains.
+	fn revoke_subdomains(domain_hash: T::Hash) {
+		for child in <Children<T>>::take(domain_hash) {
+			Self::revoke_subdomains(child);
+
+			let source = Self::domain(child).source;
+			Self::remove_reverse(source, child);
+			Self::clear_records(child);
+			<PendingTransfers<T>>::remove(child);
+			<Resolver<T>>::remove(child);
+			<Parent<T>>::remove(child);
+			Domains::mutate(|domains| *domains = domains.wrapping_sub(1));
+
+			Self::deposit_event(RawEvent::SubdomainRevoked(domain_hash, child));
+		}
 	}
 }
 
@@ -122,7 +984,47 @@ impl<T: Trait> Module<T> {
 pub trait Trait: system::Trait + balances::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-	
+	/// The currency in which the deposits of domains are reserved
+	type Currency: ReservableCurrency<Self::AccountId, Balance = <Self as balances::Trait>::Balance>;
+	/// Pricing policy of domains e.g. `LengthPricing<Self>`
+	type PriceOracle: PriceOracle<<Self as balances::Trait>::Balance>;
+	/// Number of blocks a domain is registered or renewed for
+	type RegistrationPeriod: Get<Self::BlockNumber>;
+	/// Number of blocks an auction is open for bids
+	type AuctionPeriod: Get<Self::BlockNumber>;
+	/// Number of blocks after a sealed-bid auction is closed during which the bids are revealed
+	type RevealPeriod: Get<Self::BlockNumber>;
+	/// Number of blocks at the end of a candle auction in which the auction might be closed
+	type EndingPeriod: Get<Self::BlockNumber>;
+	/// Number of blocks after the expiry of a domain during which only its source can renew it
+	type GracePeriod: Get<Self::BlockNumber>;
+	/// Number of blocks after the grace period during which an expired domain is sold with a decaying premium
+	type PremiumPeriod: Get<Self::BlockNumber>;
+	/// Premium of an expired domain at the beginning of the premium period
+	type PremiumStartPrice: Get<<Self as balances::Trait>::Balance>;
+	/// Curve of the premium during the premium period e.g. `LinearDecay`
+	type PremiumDecay: PremiumDecay<<Self as balances::Trait>::Balance, Self::BlockNumber>;
+	/// Where the premiums paid for expired domains go, `()` burns them
+	type PremiumProceeds: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	/// Part of the deposit of a sealed bid which is slashed when the bid is not revealed
+	type UnrevealedBidPenalty: Get<Perbill>;
+	/// Price of a domain with a long name, which the pricing policy is based on
+	type BasePrice: Get<<Self as balances::Trait>::Balance>;
+	/// Number of blocks before a commitment can be revealed, so that it cannot be
+	/// committed and revealed in the same block by someone watching the transaction pool
+	type MinCommitmentAge: Get<Self::BlockNumber>;
+	/// Number of blocks after which a commitment which is not revealed expires
+	type MaxCommitmentAge: Get<Self::BlockNumber>;
+	/// Number of blocks during which a proposed transfer can be accepted by the recipient
+	type TransferPeriod: Get<Self::BlockNumber>;
+	/// Maximum length of the key of a text record in bytes
+	type MaxTextKeyLength: Get<u32>;
+	/// Maximum length of the value of a text record in bytes
+	type MaxTextValueLength: Get<u32>;
+	/// Deposit reserved per byte of the key and the value of a text record, and of the RDATA of DNS records
+	type TextByteDeposit: Get<<Self as balances::Trait>::Balance>;
+	/// Maximum number of IPV4 addresses, of IPV6 addresses and of chain addresses of a domain
+	type MaxAddresses: Get<u32>;
 }
 
 
@@ -131,15 +1033,77 @@ decl_storage! {
 	trait Store for Module<T: Trait> as NameServiceModule {
 		/// Total number of domains
 		Domains get(total_domains): u64;
-		/// Hash is the blake2b hash of the domain name
-		/// In Javascript, use @polkadot/util-crypto's blake2AsHex("<domain name you want>" 256) and put the hexstring in the polkadot.js apps param.
-		/// Or use blakejs with this example.
-		/// > var blake = require('blakejs');
-		/// > console.log(blake.blake2s('hyungsukkang.dot'))
-		/// fecf3628563657233c1d29fd6589bcb792d1ce7611892490c3dd5857647006d7
-		Resolver get(domain): map T::Hash => Domain<T::AccountId, T::Balance, T::BlockNumber>;
-		/// Reverse resolver for account => domain_hash
+		/// Registration commitments made with `commit`: commitment => (committer, block of the commitment)
+		Commitments get(commitment): map T::Hash => Option<(T::AccountId, T::BlockNumber)>;
+		/// Commitments to check for expiry at a block
+		CommitmentExpiry get(expiring_commitments): map T::BlockNumber => Vec<T::Hash>;
+		/// Hash is the namehash of the normalized domain name with `T::Hashing`(blake2b 256 in this runtime), see `name::namehash`.
+		/// Every call taking a `domain_hash` expects this namehash. The node computes it with the `names_computeHash` RPC,
+		/// or in Javascript with @polkadot/util-crypto's blake2AsU8a folding the labels from the right.
+		/// Read the domains with `domain`, which migrates the domains stored in the `LegacyDomain` layout.
+		Resolver: map T::Hash => Domain<T::AccountId, T::Balance, T::BlockNumber>;
+		/// Legacy reverse resolver for account => domain_hashes, which is replaced by the owner index below.
+		/// The lists are removed as `migrate_domains` rebuilds the index from `Resolver`
 		Reverse get(account): map T::AccountId => Vec<T::Hash>;
+		/// Owner index for the reverse resolution: (account, index) => domain_hash
+		OwnedDomains get(owned_domain): double_map T::AccountId, blake2_256(u64) => Option<T::Hash>;
+		/// Position of a domain in the owner index: (account, domain_hash) => index
+		OwnedIndex get(owned_index): double_map T::AccountId, blake2_256(T::Hash) => Option<u64>;
+		/// Number of domains an account owns
+		OwnedCount get(owned_count): map T::AccountId => u64;
+		/// Name an account displays, set with `set_primary_name`. Read it with `primary_name`, which checks that
+		/// the domain still resolves to the account
+		PrimaryNames get(primary): map T::AccountId => Option<T::Hash>;
+		/// Sealed bids of the auction of a domain: (domain_hash, bidder) => sealed bid
+		SealedBids get(sealed_bid): double_map T::Hash, blake2_256(T::AccountId) => Option<SealedBid<T::Hash, T::Balance>>;
+		/// Accounts which sealed a bid in the auction of a domain
+		SealedBidders get(sealed_bidders): map T::Hash => Vec<T::AccountId>;
+		/// Highest bid of a candle auction at the blocks of its ending period: (domain_hash, block) => (bidder, bid).
+		/// The bids before the ending period are recorded at its first block
+		CandleBids get(candle_bid): double_map T::Hash, blake2_256(T::BlockNumber) => Option<(T::AccountId, T::Balance)>;
+		/// Bids escrowed by a bidder in a candle auction, which are kept until the auction is finalized
+		CandleEscrow get(candle_escrow): double_map T::Hash, blake2_256(T::AccountId) => T::Balance;
+		/// Accounts which bid in the candle auction of a domain
+		CandleBidders get(candle_bidders): map T::Hash => Vec<T::AccountId>;
+		/// Block of the ending period at which a candle auction closed, drawn once its bids are over
+		CandleClosing get(candle_closing): map T::Hash => Option<T::BlockNumber>;
+		/// Candle auctions whose closing block is drawn at a block
+		CandleDraws get(candle_draws): map T::BlockNumber => Vec<T::Hash>;
+		/// Price of premium names set by root, which overrides the length based price
+		PremiumNames get(premium_price): map T::Hash => Option<T::Balance>;
+		/// Parent domain of a subdomain, e.g. company.dot for api.company.dot
+		Parent get(parent): map T::Hash => Option<T::Hash>;
+		/// Subdomains directly under a domain
+		Children get(children): map T::Hash => Vec<T::Hash>;
+		/// Transfers proposed with `propose_transfer`: domain_hash => (source, recipient, block the proposal expires at)
+		PendingTransfers get(pending_transfer): map T::Hash => Option<(T::AccountId, T::AccountId, T::BlockNumber)>;
+		/// Proposed transfers to check for expiry at a block
+		TransferExpiry get(expiring_transfers): map T::BlockNumber => Vec<T::Hash>;
+		/// Operators who manage the records of all domains of an owner: (owner, operator) => approved
+		OperatorApprovals get(operator_approval): double_map T::AccountId, blake2_256(T::AccountId) => bool;
+		/// Operators who manage the records of a single domain of an owner: (owner, (domain_hash, operator)) => approved.
+		/// The approvals are keyed by the owner so that they lapse when the domain changes hands
+		DomainApprovals get(domain_approval): double_map T::AccountId, blake2_256((T::Hash, T::AccountId)) => bool;
+		/// Text records of a domain: (domain_hash, key) => record, e.g. `email`, `url`, `avatar` or `com.twitter`
+		TextRecords get(text): double_map T::Hash, blake2_256(BYTES) => Option<TextRecord<T::AccountId, T::Balance>>;
+		/// Keys of the text records of a domain
+		TextKeys get(text_keys): map T::Hash => Vec<BYTES>;
+		/// IPV4 addresses of a domain a.k.a. its A records
+		IPV4Records get(ipv4): map T::Hash => Vec<IPV4>;
+		/// IPV6 addresses of a domain a.k.a. its AAAA records, e.g. for IoT devices
+		IPV6Records get(ipv6): map T::Hash => Vec<IPV6>;
+		/// Addresses of a domain on other chains: (domain_hash, SLIP-44 coin type) => raw address, see `address`
+		AddressRecords get(address): double_map T::Hash, blake2_256(u32) => Option<BYTES>;
+		/// Coin types of the addresses of a domain
+		CoinTypes get(coin_types): map T::Hash => Vec<u32>;
+		/// EIP-1577 content hash of a domain pointing at IPFS, Swarm or Arweave, see `content_hash`
+		ContentHashes get(content_hash): map T::Hash => Option<BYTES>;
+		/// DNS record sets of a domain other than A and AAAA: (domain_hash, DNS type) => records, see `dns`
+		DnsRecords get(dns_records): double_map T::Hash, blake2_256(u16) => Option<RecordSet<T::AccountId, T::Balance>>;
+		/// DNS types of the record sets of a domain
+		DnsRecordTypes get(dns_record_types): map T::Hash => Vec<u16>;
+		/// DNS TTL of the A and AAAA records of a domain in seconds: (domain_hash, DNS type) => ttl
+		AddressTtls get(address_ttl): double_map T::Hash, blake2_256(u16) => Option<u32>;
 	}
 }
 
@@ -151,55 +1115,321 @@ decl_module! {
 		// this is needed only if you are using events in your module
 		fn deposit_event() = default;
 
+		/// Number of blocks a domain is registered or renewed for
+		const RegistrationPeriod: T::BlockNumber = T::RegistrationPeriod::get();
+		/// Number of blocks an auction is open for bids
+		const AuctionPeriod: T::BlockNumber = T::AuctionPeriod::get();
+		/// Number of blocks after a sealed-bid auction is closed during which the bids are revealed
+		const RevealPeriod: T::BlockNumber = T::RevealPeriod::get();
+		/// Part of the deposit of a sealed bid which is slashed when the bid is not revealed
+		const UnrevealedBidPenalty: Perbill = T::UnrevealedBidPenalty::get();
+		/// Number of blocks at the end of a candle auction in which the auction might be closed
+		const EndingPeriod: T::BlockNumber = T::EndingPeriod::get();
+		/// Number of blocks after the expiry of a domain during which only its source can renew it
+		const GracePeriod: T::BlockNumber = T::GracePeriod::get();
+		/// Number of blocks after the grace period during which an expired domain is sold with a decaying premium
+		const PremiumPeriod: T::BlockNumber = T::PremiumPeriod::get();
+		/// Premium of an expired domain at the beginning of the premium period
+		const PremiumStartPrice: T::Balance = T::PremiumStartPrice::get();
+		/// Price of a domain with a long name
+		const BasePrice: T::Balance = T::BasePrice::get();
+		/// Number of blocks before a commitment can be revealed
+		const MinCommitmentAge: T::BlockNumber = T::MinCommitmentAge::get();
+		/// Number of blocks after which a commitment which is not revealed expires
+		const MaxCommitmentAge: T::BlockNumber = T::MaxCommitmentAge::get();
+		/// Number of blocks during which a proposed transfer can be accepted by the recipient
+		const TransferPeriod: T::BlockNumber = T::TransferPeriod::get();
+		/// Maximum length of the key of a text record in bytes
+		const MaxTextKeyLength: u32 = T::MaxTextKeyLength::get();
+		/// Maximum length of the value of a text record in bytes
+		const MaxTextValueLength: u32 = T::MaxTextValueLength::get();
+		/// Deposit reserved per byte of the key and the value of a text record, and of the RDATA of DNS records
+		const TextByteDeposit: T::Balance = T::TextByteDeposit::get();
+		/// Maximum number of IPV4 addresses, of IPV6 addresses and of chain addresses of a domain
+		const MaxAddresses: u32 = T::MaxAddresses::get();
+
 ////////////////////////////////////////////////////////////////////////////////////////////////
 /// domain and reverse logics //////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////////////////	
 			
-		/// Register domain with estimated 1 year ttl blocktime(31556926000 milliseconds) and 1 milli DEV(0.001 DEV) base price
-		pub fn register_domain(origin, domain_hash: T::Hash, domain_name: BYTES) -> Result {
+		fn on_initialize(now: T::BlockNumber) {
+			Self::expire_commitments(now);
+			Self::expire_transfers(now);
+			Self::draw_candle_closings(now);
+		}
+
+		/// Commit to the registration of a domain without revealing its name.
+		/// `commitment` is computed with `commitment_of(domain_name, salt, sender)` where salt is a secret random hash
+		pub fn commit(origin, commitment: T::Hash) -> Result {
 			let sender = ensure_signed(origin)?;
-			ensure!(!<Resolver<T>>::exists(domain_hash), "The domain already exists");
-			// Make new Domain struct
-			let new_domain = Self::new_domain(domain_name, sender.clone());
+			ensure!(!<Commitments<T>>::exists(commitment), "The commitment already exists");
 
-			// Try to withdraw registration fee from the user without killing the account
-			let _ = <balances::Module<T> as Currency<_>>::withdraw(&sender.clone(), new_domain.price, WithdrawReason::Reserve, ExistenceRequirement::KeepAlive)?;			
+			let now = <system::Module<T>>::block_number();
+			<Commitments<T>>::insert(commitment, (sender.clone(), now));
 
-			<Reverse<T>>::insert(sender.clone(), vec![domain_hash]);
-			
-			// Insert new domain to the Resolver state
-			<Resolver<T>>::insert(domain_hash, new_domain.clone());
+			// Queue the commitment to be cleaned up once it cannot be revealed anymore
+			let expiry = now + T::MaxCommitmentAge::get() + T::BlockNumber::from(1);
+			<CommitmentExpiry<T>>::mutate(expiry, |commitments| commitments.push(commitment));
 
-			// Increment domain number	
-			let mut domains = Self::total_domains();
-			domains = domains.wrapping_add(1);
+			Self::deposit_event(RawEvent::Committed(sender, commitment));
 
-			// Store domain number to Domains state
-			Domains::put(domains);			
+			Ok(())
+		}
+
+		/// Reveal a committed domain and register it for the registration period at the price given by the price oracle
+		pub fn reveal(origin, domain_name: BYTES, salt: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let commitment = Self::commitment_of(&domain_name, &salt, &sender);
+			// The commitment binds the owner, so it does not matter who made it
+			let (_, committed) = Self::commitment(commitment).ok_or("The commitment does not exist")?;
+
+			// Ensure the commitment is neither too new nor too old
+			let now = <system::Module<T>>::block_number();
+			ensure!(committed + T::MinCommitmentAge::get() <= now, "The commitment is too new to be revealed");
+			ensure!(now <= committed + T::MaxCommitmentAge::get(), "The commitment is expired");
+
+			Self::register(sender, domain_name)?;
+			<Commitments<T>>::remove(commitment);
 
-			// Deposit event
-			Self::deposit_event(RawEvent::DomainRegistered(sender.clone(), new_domain.price, new_domain.ttl, new_domain.registered_date));
-			
 			Ok(())
 		}
 
-		/// Set IPV4 for existing domain
+		/// Replace the IPV4 addresses of the domain with the address
 		pub fn set_ipv4(origin, domain_hash: T::Hash, ipv4: IPV4) -> Result {
-			// Ensure that 
-			// domain exists
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv4(&sender, domain_hash, |addresses| {
+				*addresses = vec![ipv4];
+				Ok(())
+			})
+		}
+
+		/// Add an IPV4 address to the domain
+		pub fn add_ipv4(origin, domain_hash: T::Hash, ipv4: IPV4) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv4(&sender, domain_hash, |addresses| {
+				ensure!(!addresses.contains(&ipv4), "The domain already has the address");
+				addresses.push(ipv4);
+				Ok(())
+			})
+		}
+
+		/// Remove an IPV4 address from the domain
+		pub fn remove_ipv4(origin, domain_hash: T::Hash, ipv4: IPV4) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv4(&sender, domain_hash, |addresses| {
+				ensure!(addresses.contains(&ipv4), "The domain does not have the address");
+				addresses.retain(|address| *address != ipv4);
+				Ok(())
+			})
+		}
+
+		/// Replace the IPV6 addresses of the domain with the address
+		pub fn set_ipv6(origin, domain_hash: T::Hash, ipv6: IPV6) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv6(&sender, domain_hash, |addresses| {
+				*addresses = vec![ipv6];
+				Ok(())
+			})
+		}
+
+		/// Add an IPV6 address to the domain
+		pub fn add_ipv6(origin, domain_hash: T::Hash, ipv6: IPV6) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv6(&sender, domain_hash, |addresses| {
+				ensure!(!addresses.contains(&ipv6), "The domain already has the address");
+				addresses.push(ipv6);
+				Ok(())
+			})
+		}
+
+		/// Remove an IPV6 address from the domain
+		pub fn remove_ipv6(origin, domain_hash: T::Hash, ipv6: IPV6) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::update_ipv6(&sender, domain_hash, |addresses| {
+				ensure!(addresses.contains(&ipv6), "The domain does not have the address");
+				addresses.retain(|address| *address != ipv6);
+				Ok(())
+			})
+		}
+
+		/// Set the address of the domain on the chain with the SLIP-44 coin type e.g. 60 for Ethereum.
+		/// Bitcoin and Litecoin addresses are stored as their scriptPubkey, see `address`
+		pub fn set_address(origin, domain_hash: T::Hash, coin_type: u32, address: BYTES) -> Result {
+			let sender = ensure_signed(origin)?;
 			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
-			// the sender is the source of the domain
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			let address = address::to_raw(coin_type, &address)?;
+
+			let mut coin_types = Self::coin_types(domain_hash);
+			if !coin_types.contains(&coin_type) {
+				ensure!(coin_types.len() < T::MaxAddresses::get() as usize, "The domain has too many addresses");
+				coin_types.push(coin_type);
+				<CoinTypes<T>>::insert(domain_hash, coin_types);
+			}
+			<AddressRecords<T>>::insert(&domain_hash, &coin_type, address.clone());
+			Self::deposit_event(RawEvent::AddressChanged(domain_hash, coin_type, address));
+
+			Ok(())
+		}
+
+		/// Remove the address of the domain on the chain with the coin type
+		pub fn clear_address(origin, domain_hash: T::Hash, coin_type: u32) -> Result {
 			let sender = ensure_signed(origin)?;
-			let mut new_domain = Self::domain(domain_hash);
-			ensure!(sender == new_domain.source, "you are not the source of the domain");
-			
-			// Set ipv4 for new domain
-			let old_ipv4 = new_domain.ipv4;
-			new_domain.ipv4 = ipv4;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(<AddressRecords<T>>::exists(&domain_hash, &coin_type), "The domain has no address for the coin type");
+
+			<AddressRecords<T>>::remove(&domain_hash, &coin_type);
+			<CoinTypes<T>>::mutate(domain_hash, |coin_types| coin_types.retain(|c| *c != coin_type));
+			Self::deposit_event(RawEvent::AddressCleared(domain_hash, coin_type));
+
+			Ok(())
+		}
+
+		/// Point the domain at content on IPFS, Swarm or Arweave with an EIP-1577 content hash
+		pub fn set_content_hash(origin, domain_hash: T::Hash, hash: BYTES) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			content_hash::validate(&hash)?;
 
-			// Change domain data with the new one and emit event
-			<Resolver<T>>::mutate(domain_hash.clone(), |d| *d = new_domain.clone());
-			Self::deposit_event(RawEvent::SetIPV4(domain_hash, old_ipv4.to_vec(), new_domain.ipv4.to_vec()));
+			<ContentHashes<T>>::insert(domain_hash, hash.clone());
+			Self::deposit_event(RawEvent::ContentHashChanged(domain_hash, hash));
+
+			Ok(())
+		}
+
+		/// Remove the content hash of the domain
+		pub fn clear_content_hash(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(<ContentHashes<T>>::exists(domain_hash), "The domain has no content hash");
+
+			<ContentHashes<T>>::remove(domain_hash);
+			Self::deposit_event(RawEvent::ContentHashCleared(domain_hash));
+
+			Ok(())
+		}
+
+		/// Replace the DNS records of the type e.g. 15 for MX with the records given as RDATA in the DNS wire format.
+		/// The deposit for their bytes is reserved from the sender
+		pub fn set_dns_records(origin, domain_hash: T::Hash, rtype: u16, ttl: u32, records: Vec<BYTES>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(!records.is_empty(), "The record set is empty");
+			ensure!(records.len() <= dns::MAX_RECORDS, "The record set has too many records");
+			for rdata in records.iter() {
+				dns::validate(rtype, rdata)?;
+			}
+
+			// A CNAME cannot be mixed with other records of the domain
+			let rtypes = Self::dns_record_types(domain_hash);
+			if rtype == dns::TYPE_CNAME {
+				ensure!(records.len() == 1, "A domain has a single CNAME record");
+				ensure!(rtypes.iter().all(|t| *t == dns::TYPE_CNAME), "The domain has other DNS records than the CNAME");
+				ensure!(Self::ipv4(domain_hash).is_empty() && Self::ipv6(domain_hash).is_empty(), "The domain has other DNS records than the CNAME");
+				if let dns::RecordData::Cname(target) = dns::RecordData::from_wire(rtype, &records[0])? {
+					Self::ensure_no_cname_cycle(domain_hash, &target)?;
+				}
+			} else {
+				ensure!(!rtypes.contains(&dns::TYPE_CNAME), "The domain is an alias with a CNAME record");
+			}
+
+			let bytes = records.iter().map(|rdata| rdata.len() as u32).sum::<u32>();
+			let deposit = T::TextByteDeposit::get() * T::Balance::from(bytes);
+			ensure!(T::Currency::can_reserve(&sender, deposit), "You cannot afford the deposit of the DNS records");
+
+			if <DnsRecords<T>>::exists(&domain_hash, &rtype) {
+				Self::remove_dns_records(domain_hash, rtype);
+			} else {
+				<DnsRecordTypes<T>>::mutate(domain_hash, |rtypes| rtypes.push(rtype));
+			}
+			T::Currency::reserve(&sender, deposit)?;
+			<DnsRecords<T>>::insert(&domain_hash, &rtype, RecordSet { ttl, records, depositor: sender, deposit });
+			Self::deposit_event(RawEvent::DnsRecordsChanged(domain_hash, rtype, ttl));
+
+			Ok(())
+		}
+
+		/// Remove the DNS records of the type and return their deposit
+		pub fn clear_dns_records(origin, domain_hash: T::Hash, rtype: u16) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(<DnsRecords<T>>::exists(&domain_hash, &rtype), "The domain has no DNS records of the type");
+
+			Self::remove_dns_records(domain_hash, rtype);
+			<DnsRecordTypes<T>>::mutate(domain_hash, |rtypes| rtypes.retain(|t| *t != rtype));
+			Self::deposit_event(RawEvent::DnsRecordsCleared(domain_hash, rtype));
+
+			Ok(())
+		}
+
+		/// Set the DNS TTL of the A or AAAA records of the domain, which come from its IP addresses
+		pub fn set_address_ttl(origin, domain_hash: T::Hash, rtype: u16, ttl: u32) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(rtype == dns::TYPE_A || rtype == dns::TYPE_AAAA, "The DNS type is not A or AAAA");
+
+			<AddressTtls<T>>::insert(&domain_hash, &rtype, ttl);
+			Self::deposit_event(RawEvent::DnsRecordsChanged(domain_hash, rtype, ttl));
+
+			Ok(())
+		}
+
+		/// Move the domains stored in the `LegacyDomain` layout to the current layout ahead of their first read,
+		/// and rebuild the owner index from `Resolver`.
+		/// Maps cannot be iterated, so root passes the namehashes of the registered domains in batches
+		pub fn migrate_domains(origin, domain_hashes: Vec<T::Hash>) -> Result {
+			ensure_root(origin)?;
+			let mut migrated = 0u32;
+			for domain_hash in domain_hashes {
+				let moved = Self::load_domain(domain_hash).map_or(false, |(_, migrated)| migrated);
+				let indexed = Self::index_domain(domain_hash);
+				if moved || indexed {
+					migrated += 1;
+				}
+			}
+			Self::deposit_event(RawEvent::DomainsMigrated(migrated));
+
+			Ok(())
+		}
+
+		/// Set the text record of the domain under the key. The deposit for its bytes is reserved from the sender
+		pub fn set_text(origin, domain_hash: T::Hash, key: BYTES, value: BYTES) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(!key.is_empty(), "The key of the text record is empty");
+			ensure!(key.len() <= T::MaxTextKeyLength::get() as usize, "The key of the text record is too long");
+			ensure!(value.len() <= T::MaxTextValueLength::get() as usize, "The value of the text record is too long");
+			let deposit = Self::text_deposit(&key, &value);
+			ensure!(T::Currency::can_reserve(&sender, deposit), "You cannot afford the deposit of the text record");
+
+			match <TextRecords<T>>::take(&domain_hash, &key) {
+				Some(old) => T::Currency::unreserve(&old.depositor, old.deposit),
+				None => <TextKeys<T>>::mutate(domain_hash, |keys| keys.push(key.clone())),
+			}
+			T::Currency::reserve(&sender, deposit)?;
+			<TextRecords<T>>::insert(&domain_hash, &key, TextRecord { value, depositor: sender, deposit });
+			Self::deposit_event(RawEvent::TextChanged(domain_hash, key));
+
+			Ok(())
+		}
+
+		/// Remove the text record of the domain under the key and return its deposit
+		pub fn clear_text(origin, domain_hash: T::Hash, key: BYTES) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(Self::is_manager(domain_hash, &Self::domain(domain_hash), &sender), "You are not the controller or an operator of the domain");
+			ensure!(<TextRecords<T>>::exists(&domain_hash, &key), "The text record does not exist");
+
+			Self::remove_text(domain_hash, &key);
+			<TextKeys<T>>::mutate(domain_hash, |keys| keys.retain(|k| *k != key));
+			Self::deposit_event(RawEvent::TextCleared(domain_hash, key));
 
 			Ok(())
 		}
@@ -216,16 +1446,26 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 
 			let mut new_domain = Self::domain(domain_hash.clone());
-			let now = <system::Module<T>>::block_number();
-			// Ensure the sender is the source of the domain and its ttl is not expired
-			ensure!(new_domain.source == sender && now < new_domain.registered_date + new_domain.ttl, "You are either not the source of the domain or the domain is expired");
-			
-			// Extend domain TTL by a year
-			let ttl = T::BlockNumber::from(YEAR);
-			new_domain.ttl += ttl;		
+			// Ensure the sender is the source of the domain and it is not expired for longer than the grace period
+			let state = Self::domain_state(domain_hash);
+			ensure!(new_domain.source == sender && (state == DomainState::Active || state == DomainState::Grace), "You are either not the source of the domain or the domain is expired");
+
+			if let Some(parent_hash) = Self::parent(domain_hash) {
+				// Subdomains are free and are extended up to the expiry of the parent domain
+				new_domain.ttl = Self::expires_at(parent_hash) - new_domain.registered_date;
+			} else {
+				// Top up the deposit in case the registration deposit went up since the domain was registered
+				let deposit = Self::registration_deposit(&new_domain.name);
+				new_domain.price = deposit;
+				if new_domain.deposit < deposit {
+					T::Currency::reserve(&sender, deposit - new_domain.deposit)?;
+					new_domain.deposit = deposit;
+				}
 
-			// Try to withdraw price from the user account to renew the domain 
-			let _ = <balances::Module<T> as Currency<_>>::withdraw(&sender, new_domain.price, WithdrawReason::Reserve, ExistenceRequirement::KeepAlive)?;			
+				// Extend domain TTL by a registration period
+				let ttl = T::RegistrationPeriod::get();
+				new_domain.ttl += ttl;
+			}
 
 
 			// mutate domain with new_domain struct in the Domain state
@@ -236,24 +1476,33 @@ decl_module! {
 			Ok(())
 		}
 
-		pub fn claim_auction(origin, domain_hash: T::Hash) -> Result {
+		pub fn claim_auction(origin, domain_hash: T::Hash, mode: AuctionMode) -> Result {
 			let sender = ensure_signed(origin)?;
 			// Ensure that
 			// Domain does already exist
 			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			// Subdomains are managed by the source of the parent domain
+			ensure!(!<Parent<T>>::exists(domain_hash), "Subdomains cannot be auctioned");
 			// But wait, get domain data and time
  			let mut new_domain = Self::domain(domain_hash.clone());
 			let now = <system::Module<T>>::block_number();
-			// Ensure the sender is the source of the domain or its ttl is expired
-			ensure!(sender == new_domain.source || new_domain.registered_date + new_domain.ttl < now, "You are neither the source of the domain or the claimer after the domain's TTL");
+			// Ensure the sender is the source of the domain and its ttl is not expired.
+			// Expired domains are sold with buy_expired instead
+			ensure!(sender == new_domain.source && now < Self::expires_at(domain_hash), "You are either not the source of the domain or the domain is expired");
+			ensure!(!new_domain.available, "The domain is already in an auction");
 
 			
 			// Set domain available for selling
 			new_domain.available = true;
+			new_domain.auction_mode = mode;
 
-			// Set auction to be closed after 1 hour(60* 60 seconds) * 1000(milliseconds conversion) using timestamp 
-			let converted = T::BlockNumber::from(600);
-			new_domain.auction_closed = now + converted;
+			// Set auction to be closed after the auction period
+			new_domain.auction_closed = now + T::AuctionPeriod::get();
+			if mode == AuctionMode::Candle {
+				// The closing block is drawn at the first block without bids, which is never a block already initialized
+				let draw = rstd::cmp::max(new_domain.auction_closed, now + T::BlockNumber::from(1));
+				<CandleDraws<T>>::mutate(draw, |domains| domains.push(domain_hash));
+			}
 
 			// mutate domain with new_domain struct in the Domain state
 			<Resolver<T>>::mutate(domain_hash.clone(), |domain| *domain = new_domain.clone());
@@ -276,9 +1525,29 @@ decl_module! {
 			// The auction is not finalized
 			let now = <system::Module<T>>::block_number();
 			ensure!(new_domain.auction_closed > now, "The bid for the auction is already finalized");
+			ensure!(new_domain.auction_mode != AuctionMode::SealedBid, "Bids of a sealed-bid auction are made with seal_bid");
 			// The bid price is higher than the current highest bid
 			ensure!(new_domain.highest_bid < bid.clone(), "Bid higher");
-			
+			ensure!(sender != new_domain.source, "The source cannot bid on its own domain");
+
+			if new_domain.auction_mode == AuctionMode::Candle {
+				// Bidders who are outbid might still win at an earlier block, so their bids stay escrowed
+				let escrow = Self::candle_escrow(&domain_hash, &sender);
+				T::Currency::reserve(&sender, bid - escrow)?;
+				if escrow.is_zero() {
+					<CandleBidders<T>>::mutate(domain_hash, |bidders| bidders.push(sender.clone()));
+				}
+				<CandleEscrow<T>>::insert(&domain_hash, &sender, bid);
+				let block = rstd::cmp::max(now, Self::ending_start(&new_domain));
+				<CandleBids<T>>::insert(&domain_hash, &block, (sender.clone(), bid));
+			} else if sender == new_domain.bidder {
+				// Escrow the bid. A bidder raising its own bid only reserves the difference
+				T::Currency::reserve(&sender, bid - new_domain.highest_bid)?;
+			} else {
+				T::Currency::reserve(&sender, bid)?;
+				// Refund the bidder who is outbid
+				T::Currency::unreserve(&new_domain.bidder, new_domain.highest_bid);
+			}
 
 			// Set new domain data
 			new_domain.bidder = sender.clone();
@@ -291,68 +1560,304 @@ decl_module! {
 			Ok(())
 		}
 
+		/// Buy an expired domain at its registration price plus the premium, which decays during the premium period.
+		/// The first buyer to pay the current price takes the domain, and the previous source gets its deposit back
+		pub fn buy_expired(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(!<Parent<T>>::exists(domain_hash), "Subdomains cannot be bought");
+			let domain = Self::domain(domain_hash);
+			let state = Self::domain_state(domain_hash);
+			ensure!(state == DomainState::Premium || state == DomainState::Available, "The domain is not expired or is in its grace period");
+			ensure!(!domain.available, "The domain is in an auction");
+
+			let deposit = Self::registration_deposit(&domain.name);
+			let premium = Self::premium(domain_hash);
+			ensure!(T::Currency::free_balance(&sender) >= deposit + premium, "You cannot afford the domain");
+
+			// The premium is paid to the premium proceeds and the registration price is reserved as the deposit
+			let imbalance = T::Currency::withdraw(&sender, premium, WithdrawReason::Fee, ExistenceRequirement::KeepAlive)?;
+			T::PremiumProceeds::on_unbalanced(imbalance);
+			Self::change_owner(domain_hash, domain, sender.clone(), deposit + premium, deposit)?;
+			Self::deposit_event(RawEvent::ExpiredDomainBought(sender, domain_hash, deposit, premium));
+
+			Ok(())
+		}
+
+		/// Seal a bid in a sealed-bid auction. `sealed` is the hash of the SCALE encoded tuple (bid, salt),
+		/// and the deposit has to be at least the bid so that the bid cannot be revealed without being paid
+		pub fn seal_bid(origin, domain_hash: T::Hash, sealed: T::Hash, deposit: T::Balance) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let domain = Self::domain(domain_hash);
+			ensure!(domain.available && domain.auction_mode == AuctionMode::SealedBid, "The sealed-bid auction for the domain is currently not available");
+			let now = <system::Module<T>>::block_number();
+			ensure!(domain.auction_closed > now, "The bid for the auction is already finalized");
+			ensure!(sender != domain.source, "The source cannot bid on its own domain");
+			ensure!(!<SealedBids<T>>::exists(&domain_hash, &sender), "You already sealed a bid for the domain");
+
+			T::Currency::reserve(&sender, deposit)?;
+			<SealedBids<T>>::insert(&domain_hash, &sender, SealedBid { sealed, deposit, revealed: false });
+			<SealedBidders<T>>::mutate(domain_hash, |bidders| bidders.push(sender.clone()));
+			Self::deposit_event(RawEvent::BidSealed(sender, domain_hash));
+
+			Ok(())
+		}
+
+		/// Reveal a sealed bid during the reveal period of a sealed-bid auction
+		pub fn reveal_bid(origin, domain_hash: T::Hash, bid: T::Balance, salt: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let mut new_domain = Self::domain(domain_hash);
+			ensure!(new_domain.available && new_domain.auction_mode == AuctionMode::SealedBid, "The sealed-bid auction for the domain is currently not available");
+			let now = <system::Module<T>>::block_number();
+			ensure!(new_domain.auction_closed <= now && now <= Self::auction_end(&new_domain), "The sealed bids are not being revealed");
+
+			let mut sealed_bid = Self::sealed_bid(&domain_hash, &sender).ok_or("You did not seal a bid for the domain")?;
+			ensure!(!sealed_bid.revealed, "The bid is already revealed");
+			ensure!(sealed_bid.sealed == T::Hashing::hash_of(&(bid, salt)), "The bid does not match the sealed bid");
+			ensure!(bid <= sealed_bid.deposit, "The bid is higher than its deposit");
+
+			// Keep track of the two highest bids, the first revealed wins a tie
+			if bid > new_domain.highest_bid {
+				new_domain.second_bid = new_domain.highest_bid;
+				new_domain.highest_bid = bid;
+				new_domain.bidder = sender.clone();
+			} else if bid > new_domain.second_bid {
+				new_domain.second_bid = bid;
+			}
+
+			sealed_bid.revealed = true;
+			<SealedBids<T>>::insert(&domain_hash, &sender, sealed_bid);
+			<Resolver<T>>::insert(domain_hash, new_domain);
+			Self::deposit_event(RawEvent::BidRevealed(sender, domain_hash, bid));
+
+			Ok(())
+		}
+
 		pub fn finalize_auction(origin, domain_hash: T::Hash) -> Result {
 			let _sender = ensure_signed(origin)?; 
 			// Ensure that
 			// Domain does already exist
 			ensure!(<Resolver<T>>::exists(domain_hash), "The domain is not registered yet");
 			// But wait, get domain data and time
-			let mut new_domain = Self::domain(domain_hash);
+			let new_domain = Self::domain(domain_hash);
 			let now = <system::Module<T>>::block_number();
 			// The auction is available
 			ensure!(new_domain.available, "The auction for the domain is currently not available");
 			// The auction is finalized or the source wants to finalize the auction(test)
 			// TEST: If you want to test auction finalization without waiting for 1 hour, just add '|| sender == new_domain.source in ensure! macro
-			ensure!(now > new_domain.auction_closed, "The auction has not been finalized yet");
+			ensure!(now > Self::auction_end(&new_domain), "The auction has not been finalized yet");
 
-			let _ = <balances::Module<T> as Currency<_>>::transfer(&new_domain.bidder, &new_domain.source, new_domain.highest_bid);
+			match new_domain.auction_mode {
+				AuctionMode::English => Self::settle_english(domain_hash, new_domain),
+				AuctionMode::SealedBid => Self::settle_sealed_bids(domain_hash, new_domain),
+				AuctionMode::Candle => Self::settle_candle(domain_hash, new_domain),
+			}
+		}
 
+		/// Set or clear the price of a premium name. The price applies to new registrations and renewals
+		pub fn set_premium_price(origin, domain_name: BYTES, price: Option<T::Balance>) -> Result {
+			ensure_root(origin)?;
+			name::validate(&domain_name)?;
+			let domain_hash = Self::domain_hash_of(&domain_name);
+			match price {
+				Some(price) => <PremiumNames<T>>::insert(domain_hash, price),
+				None => <PremiumNames<T>>::remove(domain_hash),
+			}
+			Self::deposit_event(RawEvent::PremiumPriceSet(domain_hash, price));
 
-			let ttl = T::BlockNumber::from(YEAR);
+			Ok(())
+		}
 
-			// Remove domain hash from the prior owner's reverse registrar
-			let old_reverse = Self::account(new_domain.source.clone());
-			
-			let new_reverse = Self::remove_domain(domain_hash.clone(), old_reverse);
+		/// Give the domain back and get its deposit returned. Its subdomains are released as well
+		pub fn release_domain(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let domain = Self::domain(domain_hash);
+			ensure!(sender == domain.source, "You are not the source of the domain");
+			ensure!(!domain.available, "The domain is in an auction");
 
-			// Mutate reverse with new_reverse arrray in the Reverse state
-			<Reverse<T>>::mutate(new_domain.source.clone(), |account| *account = new_reverse.clone());
-		
-			// Set reverse for the new owner
-			// if the account is in reverse registrar
-			if <Reverse<T>>::exists(new_domain.bidder.clone()) {
-				let mut new_reverse: Vec<T::Hash> = Self::account(new_domain.bidder.clone());
-				new_reverse.push(domain_hash.clone());
-				// Mutate reverse with new_reverse arrray in the Reverse state
-				<Reverse<T>>::mutate(new_domain.bidder.clone(), |reverses: &mut Vec<T::Hash>| *reverses = new_reverse.clone());
+			Self::release(domain_hash);
+
+			Ok(())
+		}
+
+		/// Give or sell the domain to the new owner outside an auction. Its expiry and subdomains are kept
+		pub fn transfer_domain(origin, domain_hash: T::Hash, new_owner: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let domain = Self::domain(domain_hash);
+			ensure!(sender == domain.source, "You are not the source of the domain");
+			ensure!(sender != new_owner, "The domain is already owned by the new owner");
+			ensure!(!domain.available, "The domain is in an auction");
+			ensure!(Self::domain_state(domain_hash) == DomainState::Active, "The domain is expired");
+
+			Self::transfer(domain_hash, domain, new_owner)
+		}
+
+		/// Hand the management of the records of the domain to the controller, e.g. a hot key on a server.
+		/// The controller cannot renew, transfer or sell the domain
+		pub fn set_controller(origin, domain_hash: T::Hash, controller: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let mut domain = Self::domain(domain_hash);
+			ensure!(sender == domain.source, "You are not the source of the domain");
+
+			domain.controller = controller.clone();
+			<Resolver<T>>::insert(domain_hash, domain);
+			Self::deposit_event(RawEvent::ControllerChanged(domain_hash, controller));
+
+			Ok(())
+		}
+
+		/// Approve or revoke the operator to manage the records of all domains of the sender
+		pub fn set_approval_for_all(origin, operator: T::AccountId, approved: bool) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender != operator, "You cannot approve yourself");
+
+			if approved {
+				<OperatorApprovals<T>>::insert(&sender, &operator, true);
 			} else {
-				let new_reverse = vec![domain_hash];
-				<Reverse<T>>::insert(new_domain.bidder.clone(), new_reverse.clone());
+				<OperatorApprovals<T>>::remove(&sender, &operator);
 			}
+			Self::deposit_event(RawEvent::ApprovalForAll(sender, operator, approved));
 
-			// Set new domain data to bidder as source, highest_bid as price, and reinitialize rest of them 
-			new_domain.source = new_domain.bidder.clone();
-			new_domain.price = new_domain.highest_bid;
-			new_domain.available = false;
-			new_domain.ttl = ttl;
-			new_domain.registered_date = now;
-			new_domain.available = false;
-			new_domain.highest_bid = T::Balance::from(0);
-			new_domain.auction_closed = T::BlockNumber::from(0);
+			Ok(())
+		}
 
+		/// Approve or revoke the operator to manage the records of a domain of the sender
+		pub fn approve(origin, domain_hash: T::Hash, operator: T::AccountId, approved: bool) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			ensure!(sender == Self::domain(domain_hash).source, "You are not the source of the domain");
+
+			let key = (domain_hash, operator.clone());
+			if approved {
+				<DomainApprovals<T>>::insert(&sender, &key, true);
+			} else {
+				<DomainApprovals<T>>::remove(&sender, &key);
+			}
+			Self::deposit_event(RawEvent::Approval(domain_hash, sender, operator, approved));
 
+			Ok(())
+		}
 
-			// Mutate domain with new_domain struct in the Domain state
-			<Resolver<T>>::mutate(domain_hash.clone(), |domain| *domain = new_domain.clone());
-			
-			Self::deposit_event(RawEvent::AuctionFinalized(new_domain.bidder, domain_hash, new_domain.highest_bid));
+		/// Propose to transfer the domain, which only happens once the recipient accepts it with `accept_transfer`.
+		/// A new proposal replaces the pending one
+		pub fn propose_transfer(origin, domain_hash: T::Hash, to: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(domain_hash), "The domain does not exist");
+			let domain = Self::domain(domain_hash);
+			ensure!(sender == domain.source, "You are not the source of the domain");
+			ensure!(sender != to, "The domain is already owned by the new owner");
+			ensure!(!domain.available, "The domain is in an auction");
+			ensure!(Self::domain_state(domain_hash) == DomainState::Active, "The domain is expired");
+
+			let now = <system::Module<T>>::block_number();
+			let expiry = now + T::TransferPeriod::get();
+			<PendingTransfers<T>>::insert(domain_hash, (sender.clone(), to.clone(), expiry));
+			<TransferExpiry<T>>::mutate(expiry, |transfers| transfers.push(domain_hash));
+			Self::deposit_event(RawEvent::TransferProposed(domain_hash, sender, to, expiry));
+
+			Ok(())
+		}
+
+		/// Accept the transfer of the domain proposed to the sender
+		pub fn accept_transfer(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let (from, to, expiry) = Self::pending_transfer(domain_hash).ok_or("There is no pending transfer of the domain")?;
+			ensure!(sender == to, "The transfer is not proposed to you");
+			let now = <system::Module<T>>::block_number();
+			ensure!(now < expiry, "The proposed transfer is expired");
+			let domain = Self::domain(domain_hash);
+			ensure!(domain.source == from, "The domain changed hands since the transfer was proposed");
+			ensure!(!domain.available, "The domain is in an auction");
+			ensure!(Self::domain_state(domain_hash) == DomainState::Active, "The domain is expired");
+
+			Self::transfer(domain_hash, domain, sender)
+		}
+
+		/// Withdraw the proposed transfer of the domain
+		pub fn cancel_transfer(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let (from, _, _) = Self::pending_transfer(domain_hash).ok_or("There is no pending transfer of the domain")?;
+			ensure!(sender == from, "You did not propose the transfer");
+
+			<PendingTransfers<T>>::remove(domain_hash);
+			Self::deposit_event(RawEvent::TransferCancelled(domain_hash, sender));
+
+			Ok(())
+		}
+
+		/// Create a subdomain e.g. api.company.dot under company.dot for the owner.
+		/// Only the source of the parent domain can create subdomains, and they expire with the parent domain.
+		/// A subdomain which is expired for longer than the grace period is replaced.
+		/// The label can be a service label like `_tcp`, for the record sets of `_sip._tcp.company.dot`.
+		pub fn create_subdomain(origin, parent_hash: T::Hash, label: BYTES, owner: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Resolver<T>>::exists(parent_hash), "The parent domain does not exist");
+			let parent = Self::domain(parent_hash);
+			ensure!(sender == parent.source, "You are not the source of the parent domain");
+			let now = <system::Module<T>>::block_number();
+			let parent_expiry = Self::expires_at(parent_hash);
+			ensure!(now < parent_expiry, "The parent domain is expired");
+
+			// The full name is validated as a whole so that it stays within the length limits
+			ensure!(!label.contains(&b'.'), "The label of a subdomain cannot contain a dot");
+			let domain_name = name::join(&label, &parent.name);
+			name::validate_owner(&domain_name)?;
+			let domain_hash = name::subnode::<T::Hashing>(&parent_hash, &label);
+			if <Resolver<T>>::exists(domain_hash) {
+				let state = Self::domain_state(domain_hash);
+				ensure!(state == DomainState::Premium || state == DomainState::Available, "The domain already exists");
+				ensure!(!Self::domain(domain_hash).available, "The domain is in an auction");
+				Self::release(domain_hash);
+			}
+
+			let mut new_domain = Self::new_domain(domain_name, owner.clone());
+			new_domain.price = T::Balance::from(0);
+			new_domain.ttl = parent_expiry - now;
+
+			<Resolver<T>>::insert(domain_hash, new_domain);
+			<Parent<T>>::insert(domain_hash, parent_hash);
+			<Children<T>>::mutate(parent_hash, |children| children.push(domain_hash));
+			Self::add_reverse(owner.clone(), domain_hash);
+			Domains::mutate(|domains| *domains = domains.wrapping_add(1));
+
+			Self::deposit_event(RawEvent::SubdomainCreated(parent_hash, domain_hash, owner));
+
+			Ok(())
+		}
+
+		/// Display the domain as the name of the sender, who must be its source or controller
+		pub fn set_primary_name(origin, domain_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::resolves(domain_hash), "The domain does not exist or is expired");
+			let domain = Self::domain(domain_hash);
+			ensure!(sender == domain.source || sender == domain.controller, "You are not the source or the controller of the domain");
+
+			<PrimaryNames<T>>::insert(sender.clone(), domain_hash);
+			Self::deposit_event(RawEvent::PrimaryNameSet(sender, domain_hash));
+
+			Ok(())
+		}
+
+		/// Stop displaying a name for the sender
+		pub fn clear_primary_name(origin) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<PrimaryNames<T>>::exists(sender.clone()), "You have no primary name");
+
+			<PrimaryNames<T>>::remove(sender.clone());
+			Self::deposit_event(RawEvent::PrimaryNameCleared(sender));
 
 			Ok(())
 		}
 
-		pub fn reverse_resolve(_origin, account_id: T::AccountId) -> Result {
-			ensure!(<Reverse<T>>::exists(account_id.clone()), "The account have not registered or owned any domain");
-			let domains = Self::account(account_id.clone());
+		/// Emit a page of the domains owned by the account, see `domains_of`
+		pub fn reverse_resolve(_origin, account_id: T::AccountId, start: u64, limit: u64) -> Result {
+			ensure!(Self::owned_count(account_id.clone()) > 0, "The account have not registered or owned any domain");
+			let domains = Self::domains_of(account_id.clone(), start, limit);
 			Self::deposit_event(RawEvent::ReverseResolved(account_id, domains));
 
 			Ok(())			
@@ -364,12 +1869,40 @@ decl_event!(
 	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, <T as system::Trait>::Hash, <T as balances::Trait>::Balance, <T as system::Trait>::BlockNumber
  {
 		DomainRegistered(AccountId, Balance, BlockNumber, BlockNumber),
-		SetIPV4(Hash, Vec<u8>, Vec<u8>),
+		SetIPV4(Hash, Vec<IPV4>),
+		SetIPV6(Hash, Vec<IPV6>),
 		NewAuction(AccountId, Hash, BlockNumber, BlockNumber), 
 		NewBid(AccountId, Hash, Balance),
 		AuctionFinalized(AccountId, Hash, Balance),
 		DomainResolved(Hash, AccountId, Balance, bool, Balance, AccountId, BlockNumber),
 		ReverseResolved(AccountId, Vec<Hash>),
 		DomainRenewed(Hash, AccountId, BlockNumber),
+		Committed(AccountId, Hash),
+		SubdomainCreated(Hash, Hash, AccountId),
+		SubdomainRevoked(Hash, Hash),
+		DomainReleased(Hash, AccountId, Balance),
+		PremiumPriceSet(Hash, Option<Balance>),
+		AuctionFailed(Hash, AccountId),
+		BidSealed(AccountId, Hash),
+		BidRevealed(AccountId, Hash, Balance),
+		CandleClosed(Hash, BlockNumber),
+		ExpiredDomainBought(AccountId, Hash, Balance, Balance),
+		DomainTransferred(Hash, AccountId, AccountId),
+		TransferProposed(Hash, AccountId, AccountId, BlockNumber),
+		TransferCancelled(Hash, AccountId),
+		ControllerChanged(Hash, AccountId),
+		ApprovalForAll(AccountId, AccountId, bool),
+		Approval(Hash, AccountId, AccountId, bool),
+		TextChanged(Hash, Vec<u8>),
+		TextCleared(Hash, Vec<u8>),
+		DomainsMigrated(u32),
+		AddressChanged(Hash, u32, Vec<u8>),
+		AddressCleared(Hash, u32),
+		ContentHashChanged(Hash, Vec<u8>),
+		ContentHashCleared(Hash),
+		PrimaryNameSet(AccountId, Hash),
+		PrimaryNameCleared(AccountId),
+		DnsRecordsChanged(Hash, u16, u32),
+		DnsRecordsCleared(Hash, u16),
 	}
 );
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! Validation of domain names and the namehash algorithm which turns them into `Resolver` keys.
+//!
+//! The runtime does not do the UTS-46 mapping itself since the tables are too big for the wasm
+//! blob. Clients have to map names before submitting them (e.g. with `idna` or `tr46` in JS),
+//! and the runtime only accepts names which are already in the mapped form, so that one domain
+//! can never be registered under two different spellings. Since the mapping cannot be checked either,
+//! only the letters of a fixed set of scripts which the mapping keeps as they are can be used, and no
+//! compatibility characters like fullwidth forms or ligatures. Punycode(`xn--`) labels are decoded
+//! by clients before they are submitted.
+
+use support::dispatch::Result;
+use sr_primitives::traits::Hash;
+use rstd::prelude::*;
+use rstd::str;
+
+/// Maximum length of a whole domain name in bytes, same as DNS
+pub const MAX_NAME_LENGTH: usize = 253;
+/// Maximum length of a single label in bytes, same as DNS
+pub const MAX_LABEL_LENGTH: usize = 63;
+
+/// Ranges of the characters other than ASCII which can appear in a label: the lowercase letters of Latin, Greek,
+/// Cyrillic, Armenian, Hebrew, Arabic, Thai, Japanese kana, CJK ideographs and Hangul syllables.
+/// Characters which the mapping changes are left out, e.g. the digraphs `ǆ` and `ǅ`, `ĳ`, long s and the kana voicing marks
+const LETTERS: &[(u32, u32)] = &[
+	(0x00df, 0x00f6), (0x00f8, 0x00ff),
+	(0x0100, 0x0132), (0x0134, 0x013f), (0x0141, 0x0148), (0x014a, 0x017e),
+	(0x0180, 0x01c3), (0x01cd, 0x01f0), (0x01f4, 0x024f),
+	(0x03ac, 0x03ce),
+	(0x0430, 0x052f),
+	(0x0561, 0x0586),
+	(0x05d0, 0x05ea),
+	(0x0620, 0x063f), (0x0641, 0x064a), (0x0671, 0x0674), (0x0679, 0x06d3),
+	(0x0e01, 0x0e32), (0x0e34, 0x0e3a), (0x0e40, 0x0e4e),
+	(0x3041, 0x3096), (0x309d, 0x309e), (0x30a1, 0x30fa), (0x30fc, 0x30fe),
+	(0x3400, 0x4dbf), (0x4e00, 0x9fff),
+	(0xac00, 0xd7a3),
+];
+
+/// Ensure that the name is normalized and within length limits.
+/// A normalized name is valid UTF-8 made of dot separated labels, which only contain
+/// lowercase letters, digits and hyphens. Hyphens are not allowed at the start or the end of a label,
+/// nor at its third and fourth positions, which are reserved for encodings like the `xn--` of punycode.
+pub fn validate(name: &[u8]) -> Result {
+	ensure_length(name)?;
+	let name = str::from_utf8(name).map_err(|_| "The domain name is not valid UTF-8")?;
+	for label in name.split('.') {
+		validate_label(label)?;
+	}
+
+	Ok(())
+}
+
+/// Ensure that the name is a normalized DNS owner name: a domain name which may be prefixed with
+/// service labels, e.g. `_sip._tcp.company.dot` for the SRV records of SIP over TCP or `_443._tcp` for TLSA.
+/// A service label is an underscore followed by lowercase ASCII letters, digits and inner hyphens.
+pub fn validate_owner(name: &[u8]) -> Result {
+	ensure_length(name)?;
+	let name = str::from_utf8(name).map_err(|_| "The domain name is not valid UTF-8")?;
+	let mut labels = name.split('.').peekable();
+	while let Some(label) = labels.peek().filter(|label| label.starts_with('_')) {
+		validate_service_label(&label[1..])?;
+		labels.next();
+	}
+	if labels.peek().is_none() {
+		return Err("The service labels of the domain name are not followed by a domain name");
+	}
+	for label in labels {
+		validate_label(label)?;
+	}
+
+	Ok(())
+}
+
+/// Ensure that a service label without its underscore e.g. `tcp` of `_tcp` is normalized
+fn validate_service_label(label: &str) -> Result {
+	if label.is_empty() || label.len() >= MAX_LABEL_LENGTH {
+		return Err("The domain name has a service label which is empty or too long");
+	}
+	if label.starts_with('-') || label.ends_with('-') {
+		return Err("A label of the domain name starts or ends with a hyphen");
+	}
+	if !label.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-') {
+		return Err("The domain name is not normalized");
+	}
+
+	Ok(())
+}
+
+/// Ensure that a single label e.g. `api` of `api.company.dot` is normalized
+pub fn validate_label(label: &str) -> Result {
+	if label.is_empty() {
+		return Err("The domain name has an empty label");
+	}
+	if label.len() > MAX_LABEL_LENGTH {
+		return Err("The domain name has a label which is too long");
+	}
+	if label.starts_with('-') || label.ends_with('-') {
+		return Err("A label of the domain name starts or ends with a hyphen");
+	}
+	if label.as_bytes().get(2..4) == Some(b"--") {
+		return Err("A label of the domain name has hyphens at its third and fourth positions");
+	}
+	for c in label.chars() {
+		if !is_normalized(c) {
+			return Err("The domain name is not normalized");
+		}
+	}
+
+	Ok(())
+}
+
+/// Namehash of the domain name, the key of the domain in the runtime.
+///
+/// The algorithm is the recursive namehash of ENS with the hashing of the runtime(blake2b 256) instead of keccak 256:
+/// > namehash('') = 0x00...00 (32 zero bytes)
+/// > namehash(label + '.' + parent) = hash(namehash(parent) ++ hash(label))
+/// so namehash('api.company.dot') = hash(hash(hash(0x00...00 ++ hash('dot')) ++ hash('company')) ++ hash('api'))
+pub fn namehash<H: Hash>(name: &[u8]) -> H::Output {
+	let mut node = H::Output::default();
+	if name.is_empty() {
+		return node;
+	}
+	for label in name.rsplit(|c| *c == b'.') {
+		node = subnode::<H>(&node, label);
+	}
+	node
+}
+
+/// Namehash of the subdomain with the label under the domain with the given namehash
+pub fn subnode<H: Hash>(parent: &H::Output, label: &[u8]) -> H::Output {
+	let label_hash = H::hash(label);
+	let mut data = Vec::with_capacity(64);
+	data.extend_from_slice(parent.as_ref());
+	data.extend_from_slice(label_hash.as_ref());
+	H::hash(&data)
+}
+
+/// Number of characters in the first label of a validated name e.g. 3 for `abc.dot`
+pub fn label_length(name: &[u8]) -> usize {
+	let label = name.split(|c| *c == b'.').next().unwrap_or(&[]);
+	str::from_utf8(label).map(|label| label.chars().count()).unwrap_or(label.len())
+}
+
+/// Name of the parent domain e.g. `company.dot` for `api.company.dot`, if the name has more than one label
+pub fn parent(name: &[u8]) -> Option<&[u8]> {
+	name.iter().position(|c| *c == b'.').map(|dot| &name[dot + 1..])
+}
+
+/// Name of the subdomain with the label under the parent domain
+pub fn join(label: &[u8], parent: &[u8]) -> Vec<u8> {
+	let mut name = Vec::with_capacity(label.len() + 1 + parent.len());
+	name.extend_from_slice(label);
+	name.push(b'.');
+	name.extend_from_slice(parent);
+	name
+}
+
+fn ensure_length(name: &[u8]) -> Result {
+	if name.is_empty() {
+		return Err("The domain name is empty");
+	}
+	if name.len() > MAX_NAME_LENGTH {
+		return Err("The domain name is too long");
+	}
+
+	Ok(())
+}
+
+/// Whether the character can appear in a label after UTS-46 mapping
+fn is_normalized(c: char) -> bool {
+	if c.is_ascii() {
+		return c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+	}
+	// Mapping lowercases letters, removes spaces and symbols, and replaces compatibility characters
+	let code = c as u32;
+	LETTERS.iter().any(|(start, end)| *start <= code && code <= *end)
+		&& c.is_alphanumeric()
+		&& c.to_lowercase().eq(rstd::iter::once(c))
+}
@@ -1,12 +1,12 @@
 /// tests for this module
 #[cfg(test)]
 mod tests {
-	use super::*;
+	use super::super::*;
 
 	use runtime_io::with_externalities;
 	use primitives::{H256, Blake2Hasher};
-	use support::{impl_outer_origin, assert_ok, parameter_types};
-	use sr_primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use support::{impl_outer_origin, assert_ok, assert_err, parameter_types};
+	use sr_primitives::{traits::{BlakeTwo256, IdentityLookup, OnInitialize}, testing::Header};
 	use sr_primitives::weights::Weight;
 	use sr_primitives::Perbill;
 
@@ -24,6 +24,22 @@ mod tests {
 		pub const MaximumBlockWeight: Weight = 1024;
 		pub const MaximumBlockLength: u32 = 2 * 1024;
 		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+		pub const RegistrationPeriod: u64 = 1000;
+		pub const AuctionPeriod: u64 = 10;
+		pub const RevealPeriod: u64 = 10;
+		pub const EndingPeriod: u64 = 5;
+		pub const GracePeriod: u64 = 50;
+		pub const PremiumPeriod: u64 = 100;
+		pub const PremiumStartPrice: u128 = 100_000;
+		pub const UnrevealedBidPenalty: Perbill = Perbill::from_percent(50);
+		pub const BasePrice: u128 = 1000;
+		pub const MinCommitmentAge: u64 = 2;
+		pub const MaxCommitmentAge: u64 = 20;
+		pub const TransferPeriod: u64 = 30;
+		pub const MaxTextKeyLength: u32 = 16;
+		pub const MaxTextValueLength: u32 = 64;
+		pub const TextByteDeposit: u128 = 10;
+		pub const MaxAddresses: u32 = 2;
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -46,6 +62,26 @@ mod tests {
 
 	impl Trait for Test  {
 		type Event = ();
+		type Currency = balances::Module<Test>;
+		type PriceOracle = LengthPricing<Test>;
+		type RegistrationPeriod = RegistrationPeriod;
+		type AuctionPeriod = AuctionPeriod;
+		type RevealPeriod = RevealPeriod;
+		type EndingPeriod = EndingPeriod;
+		type GracePeriod = GracePeriod;
+		type PremiumPeriod = PremiumPeriod;
+		type PremiumStartPrice = PremiumStartPrice;
+		type PremiumDecay = LinearDecay;
+		type PremiumProceeds = ();
+		type UnrevealedBidPenalty = UnrevealedBidPenalty;
+		type BasePrice = BasePrice;
+		type MinCommitmentAge = MinCommitmentAge;
+		type MaxCommitmentAge = MaxCommitmentAge;
+		type TransferPeriod = TransferPeriod;
+		type MaxTextKeyLength = MaxTextKeyLength;
+		type MaxTextValueLength = MaxTextValueLength;
+		type TextByteDeposit = TextByteDeposit;
+		type MaxAddresses = MaxAddresses;
 	}
 
 	impl timestamp::Trait for Test {
@@ -71,11 +107,28 @@ mod tests {
 	}
 	
 	type NamingServiceModule = Module<Test>;
+	type Balances = balances::Module<Test>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
 	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-		system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1_000_000_000_000), (2, 1_000_000_000_000), (3, 1_000_000_000_000), (4, 1_000_000_000_000)],
+			vesting: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	// Commit to a domain, wait until the commitment can be revealed and reveal it
+	fn register(who: u64, name: &[u8]) -> H256 {
+		let salt = H256([7; 32]);
+		let commitment = NamingServiceModule::commitment_of(&name.to_vec(), &salt, &who);
+		let now = system::Module::<Test>::block_number();
+		assert_ok!(NamingServiceModule::commit(Origin::signed(who), commitment));
+		system::Module::<Test>::set_block_number(now + MinCommitmentAge::get());
+		assert_ok!(NamingServiceModule::reveal(Origin::signed(who), name.to_vec(), salt));
+		NamingServiceModule::domain_hash_of(name)
 	}
 
 	#[test]
@@ -93,23 +146,1259 @@ mod tests {
 	fn test_register_domain() {
 		with_externalities(&mut new_test_ext(), || {
 			let alice = 1u64;
-			let dummy_hash = H256([2; 32]);
-			assert_ok!(NamingServiceModule::register_domain(Origin::signed(alice), dummy_hash));
-			assert_eq!(NamingServiceModule::domain(dummy_hash).source, alice);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, alice);
+			assert_eq!(NamingServiceModule::total_domains(), 1);
+		});
+	}
+
+	#[test]
+	fn test_reveal_requires_minimum_commitment_age() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let name = b"hyungsukkang.dot".to_vec();
+			let salt = H256([7; 32]);
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(NamingServiceModule::commit(Origin::signed(alice), NamingServiceModule::commitment_of(&name, &salt, &alice)));
+			assert_err!(NamingServiceModule::reveal(Origin::signed(alice), name.clone(), salt), "The commitment is too new to be revealed");
+
+			// Someone else can't reveal the name with the same salt since the commitment includes the owner
+			system::Module::<Test>::set_block_number(1 + MinCommitmentAge::get());
+			assert_err!(NamingServiceModule::reveal(Origin::signed(2), name.clone(), salt), "The commitment does not exist");
+			assert_ok!(NamingServiceModule::reveal(Origin::signed(alice), name, salt));
+		});
+	}
+
+	#[test]
+	fn test_copied_commitment_cannot_steal_name() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let name = b"hyungsukkang.dot".to_vec();
+			let salt = H256([7; 32]);
+			let commitment = NamingServiceModule::commitment_of(&name, &salt, &alice);
+			system::Module::<Test>::set_block_number(1);
+
+			// Bob copies the commitment of alice from the transaction pool and gets it in first
+			assert_ok!(NamingServiceModule::commit(Origin::signed(bob), commitment));
+			assert_err!(NamingServiceModule::commit(Origin::signed(alice), commitment), "The commitment already exists");
+
+			// The commitment still registers the name for alice, and bob cannot reveal it for himself
+			system::Module::<Test>::set_block_number(1 + MinCommitmentAge::get());
+			assert_err!(NamingServiceModule::reveal(Origin::signed(bob), name.clone(), salt), "The commitment does not exist");
+			assert_ok!(NamingServiceModule::reveal(Origin::signed(alice), name.clone(), salt));
+			assert_eq!(NamingServiceModule::domain(NamingServiceModule::domain_hash_of(&name)).source, alice);
+			assert!(NamingServiceModule::domains_of(bob, 0, MAX_PAGE_SIZE).is_empty());
+		});
+	}
+
+	#[test]
+	fn test_commitment_expires() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let name = b"hyungsukkang.dot".to_vec();
+			let salt = H256([7; 32]);
+			let commitment = NamingServiceModule::commitment_of(&name, &salt, &alice);
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(NamingServiceModule::commit(Origin::signed(alice), commitment));
+
+			let expiry = 1 + MaxCommitmentAge::get() + 1;
+			system::Module::<Test>::set_block_number(expiry);
+			assert_err!(NamingServiceModule::reveal(Origin::signed(alice), name, salt), "The commitment is expired");
+
+			// The commitment is cleaned up at the beginning of the expiry block
+			NamingServiceModule::on_initialize(expiry);
+			assert_eq!(NamingServiceModule::commitment(commitment), None);
+		});
+	}
+
+	#[test]
+	fn test_name_validation() {
+		assert_ok!(name::validate(b"hyungsukkang.dot"));
+		assert_ok!(name::validate(b"my-site2.dot"));
+		assert_ok!(name::validate("caf\u{e9}.dot".as_bytes()));
+		assert_err!(name::validate(b"HyungsukKang.dot"), "The domain name is not normalized");
+		assert_err!(name::validate("CAF\u{c9}.dot".as_bytes()), "The domain name is not normalized");
+		assert_err!(name::validate(b"hyungsuk kang.dot"), "The domain name is not normalized");
+		assert_err!(name::validate(b"hyungsuk\x07.dot"), "The domain name is not normalized");
+		assert_err!(name::validate(b"-hyungsukkang.dot"), "A label of the domain name starts or ends with a hyphen");
+		assert_err!(name::validate(b"hyungsukkang..dot"), "The domain name has an empty label");
+		assert_err!(name::validate(&[b'a'; 64]), "The domain name has a label which is too long");
+		assert_err!(name::validate(&[0xff, 0xfe]), "The domain name is not valid UTF-8");
+	}
+
+	#[test]
+	fn test_name_validation_rejects_other_spellings() {
+		// Only the first spelling of each name can be registered
+		assert_ok!(name::validate("caf\u{e9}.dot".as_bytes()));
+		assert_err!(name::validate(b"xn--caf-dma.dot"), "A label of the domain name has hyphens at its third and fourth positions");
+		assert_err!(name::validate(b"ab--cd.dot"), "A label of the domain name has hyphens at its third and fourth positions");
+		assert_err!(name::validate("cafe\u{301}.dot".as_bytes()), "The domain name is not normalized");
+		// fullwidth digit
+		assert_ok!(name::validate(b"1.dot"));
+		assert_err!(name::validate("\u{ff11}.dot".as_bytes()), "The domain name is not normalized");
+		// ligature
+		assert_ok!(name::validate(b"fix.dot"));
+		assert_err!(name::validate("\u{fb01}x.dot".as_bytes()), "The domain name is not normalized");
+		// titlecase and lowercase digraphs
+		assert_ok!(name::validate("d\u{17e}a.dot".as_bytes()));
+		assert_err!(name::validate("\u{1c5}a.dot".as_bytes()), "The domain name is not normalized");
+		assert_err!(name::validate("\u{1c6}a.dot".as_bytes()), "The domain name is not normalized");
+
+		assert_ok!(name::validate("\u{d55c}\u{ae00}.dot".as_bytes()));
+		assert_ok!(name::validate("\u{3b5}\u{3bb}\u{3bb}\u{3ac}\u{3b4}\u{3b1}.dot".as_bytes()));
+	}
+
+	#[test]
+	fn test_service_label_validation() {
+		assert_ok!(name::validate_owner(b"_sip._tcp.hyungsukkang.dot"));
+		assert_ok!(name::validate_owner(b"_443._tcp.hyungsukkang.dot"));
+		assert_ok!(name::validate_owner(b"_acme-challenge.hyungsukkang.dot"));
+		assert_ok!(name::validate_owner("_http._tcp.caf\u{e9}.dot".as_bytes()));
+		assert_ok!(name::validate_owner(b"hyungsukkang.dot"));
+		// Service labels are only for DNS owner names
+		assert_err!(name::validate(b"_tcp.hyungsukkang.dot"), "The domain name is not normalized");
+		// Service labels come before the domain name
+		assert_err!(name::validate_owner(b"www._tcp.hyungsukkang.dot"), "The domain name is not normalized");
+		assert_err!(name::validate_owner(b"_sip._tcp"), "The service labels of the domain name are not followed by a domain name");
+		assert_err!(name::validate_owner(b"_.hyungsukkang.dot"), "The domain name has a service label which is empty or too long");
+		assert_err!(name::validate_owner(b"_Sip._tcp.hyungsukkang.dot"), "The domain name is not normalized");
+		assert_err!(name::validate_owner(b"__sip.hyungsukkang.dot"), "The domain name is not normalized");
+		assert_err!(name::validate_owner(b"_-sip.hyungsukkang.dot"), "A label of the domain name starts or ends with a hyphen");
+	}
+
+	#[test]
+	fn test_namehash() {
+		assert_eq!(name::namehash::<BlakeTwo256>(b""), H256::zero());
+
+		let dot = BlakeTwo256::hash(&[H256::zero().as_bytes(), BlakeTwo256::hash(b"dot").as_bytes()].concat());
+		assert_eq!(name::namehash::<BlakeTwo256>(b"dot"), dot);
+
+		let company = BlakeTwo256::hash(&[dot.as_bytes(), BlakeTwo256::hash(b"company").as_bytes()].concat());
+		assert_eq!(name::namehash::<BlakeTwo256>(b"company.dot"), company);
+		assert_eq!(name::subnode::<BlakeTwo256>(&company, b"api"), name::namehash::<BlakeTwo256>(b"api.company.dot"));
+	}
+
+	#[test]
+	fn test_reveal_rejects_unnormalized_name() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let name = b"HyungsukKang.dot".to_vec();
+			let salt = H256([7; 32]);
+			assert_ok!(NamingServiceModule::commit(Origin::signed(alice), NamingServiceModule::commitment_of(&name, &salt, &alice)));
+			system::Module::<Test>::set_block_number(MinCommitmentAge::get());
+			assert_err!(NamingServiceModule::reveal(Origin::signed(alice), name, salt), "The domain name is not normalized");
+			assert_eq!(NamingServiceModule::total_domains(), 0);
+		});
+	}
+
+	#[test]
+	fn test_create_subdomain() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let parent_hash = register(alice, b"company.dot");
+			assert_err!(NamingServiceModule::create_subdomain(Origin::signed(bob), parent_hash, b"api".to_vec(), bob), "You are not the source of the parent domain");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), bob));
+
+			let domain_hash = NamingServiceModule::domain_hash_of(b"api.company.dot");
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, bob);
+			assert_eq!(NamingServiceModule::parent(domain_hash), Some(parent_hash));
+			assert_eq!(NamingServiceModule::children(parent_hash), vec![domain_hash]);
+			assert_eq!(NamingServiceModule::expires_at(domain_hash), NamingServiceModule::expires_at(parent_hash));
+
+			// The subdomain cannot be registered by someone else through commit and reveal
+			let salt = H256([7; 32]);
+			let name = b"www.company.dot".to_vec();
+			assert_ok!(NamingServiceModule::commit(Origin::signed(bob), NamingServiceModule::commitment_of(&name, &salt, &bob)));
+			system::Module::<Test>::set_block_number(system::Module::<Test>::block_number() + MinCommitmentAge::get());
+			assert_err!(NamingServiceModule::reveal(Origin::signed(bob), name, salt), "Subdomains can only be created by the source of the parent domain");
+		});
+	}
+
+	#[test]
+	fn test_only_second_level_domains_can_be_registered() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			register(alice, b"company.dot");
+
+			// Deeper names are created by the source of the parent domain, even when the parent is not registered
+			let salt = H256([7; 32]);
+			for name in vec![b"x.api.company.dot".to_vec(), b"api.other.dot".to_vec()] {
+				assert_ok!(NamingServiceModule::commit(Origin::signed(bob), NamingServiceModule::commitment_of(&name, &salt, &bob)));
+				system::Module::<Test>::set_block_number(system::Module::<Test>::block_number() + MinCommitmentAge::get());
+				assert_err!(NamingServiceModule::reveal(Origin::signed(bob), name, salt), "Subdomains can only be created by the source of the parent domain");
+			}
+			assert_eq!(NamingServiceModule::total_domains(), 1);
+		});
+	}
+
+	#[test]
+	fn test_expired_subdomain_is_reclaimed() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let parent_hash = register(alice, b"company.dot");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), charlie));
+			let domain_hash = NamingServiceModule::domain_hash_of(b"api.company.dot");
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			assert_err!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), bob), "The domain already exists");
+
+			// The parent is renewed but the subdomain is not, so it expires on its own
+			assert_ok!(NamingServiceModule::renew(Origin::signed(alice), parent_hash));
+			system::Module::<Test>::set_block_number(expiry);
+			assert_err!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), bob), "The domain already exists");
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get());
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), bob));
+
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, bob);
+			assert_eq!(NamingServiceModule::children(parent_hash), vec![domain_hash]);
+			assert!(NamingServiceModule::domains_of(charlie, 0, MAX_PAGE_SIZE).is_empty());
+			assert_eq!(NamingServiceModule::domains_of(bob, 0, MAX_PAGE_SIZE), vec![domain_hash]);
+			assert_eq!(NamingServiceModule::total_domains(), 2);
+		});
+	}
+
+	#[test]
+	fn test_subdomains_are_revoked_when_parent_changes_hands() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let parent_hash = register(alice, b"company.dot");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), charlie));
+			let domain_hash = NamingServiceModule::domain_hash_of(b"api.company.dot");
+
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), parent_hash, AuctionMode::English));
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), parent_hash, 1000));
+			system::Module::<Test>::set_block_number(NamingServiceModule::domain(parent_hash).auction_closed + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), parent_hash));
+
+			assert_eq!(NamingServiceModule::domain(parent_hash).source, bob);
+			assert!(!<Resolver<Test>>::exists(domain_hash));
+			assert!(NamingServiceModule::domains_of(charlie, 0, MAX_PAGE_SIZE).is_empty());
+			assert_eq!(NamingServiceModule::total_domains(), 1);
+		});
+	}
+
+	#[test]
+	fn test_registration_reserves_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let deposit = NamingServiceModule::domain(domain_hash).deposit;
+			assert_eq!(deposit, BasePrice::get());
+			assert_eq!(NamingServiceModule::domain(domain_hash).price, deposit);
+			assert_eq!(Balances::reserved_balance(&alice), deposit);
+			assert_eq!(Balances::free_balance(&alice), 1_000_000_000_000 - deposit);
+
+			// Renewing keeps the same deposit
+			assert_ok!(NamingServiceModule::renew(Origin::signed(alice), domain_hash));
+			assert_eq!(Balances::reserved_balance(&alice), deposit);
+		});
+	}
+
+	#[test]
+	fn test_release_domain() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_err!(NamingServiceModule::release_domain(Origin::signed(bob), domain_hash), "You are not the source of the domain");
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), domain_hash));
+
+			assert!(!<Resolver<Test>>::exists(domain_hash));
+			assert!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE).is_empty());
+			assert_eq!(NamingServiceModule::total_domains(), 0);
+			assert_eq!(Balances::reserved_balance(&alice), 0);
+			assert_eq!(Balances::free_balance(&alice), 1_000_000_000_000);
+		});
+	}
+
+	#[test]
+	fn test_transfer_domain() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_err!(NamingServiceModule::transfer_domain(Origin::signed(bob), domain_hash, bob), "You are not the source of the domain");
+			assert_ok!(NamingServiceModule::transfer_domain(Origin::signed(alice), domain_hash, bob));
+
+			let transferred = NamingServiceModule::domain(domain_hash);
+			assert_eq!(transferred.source, bob);
+			assert_eq!(NamingServiceModule::expires_at(domain_hash), domain.registered_date + domain.ttl);
+			assert!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE).is_empty());
+			assert_eq!(NamingServiceModule::domains_of(bob, 0, MAX_PAGE_SIZE), vec![domain_hash]);
+			assert_eq!(Balances::reserved_balance(&alice), 0);
+			assert_eq!(Balances::reserved_balance(&bob), domain.deposit);
+		});
+	}
+
+	#[test]
+	fn test_transfer_domain_in_auction_or_expired() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert_err!(NamingServiceModule::transfer_domain(Origin::signed(alice), domain_hash, bob), "The domain is in an auction");
+
+			let other_hash = register(alice, b"company.dot");
+			system::Module::<Test>::set_block_number(NamingServiceModule::expires_at(other_hash));
+			assert_err!(NamingServiceModule::transfer_domain(Origin::signed(alice), other_hash, bob), "The domain is expired");
+		});
+	}
+
+	#[test]
+	fn test_two_step_transfer() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_err!(NamingServiceModule::accept_transfer(Origin::signed(bob), domain_hash), "There is no pending transfer of the domain");
+			assert_ok!(NamingServiceModule::propose_transfer(Origin::signed(alice), domain_hash, bob));
+			let expiry = system::Module::<Test>::block_number() + TransferPeriod::get();
+			assert_eq!(NamingServiceModule::pending_transfer(domain_hash), Some((alice, bob, expiry)));
+
+			assert_err!(NamingServiceModule::accept_transfer(Origin::signed(charlie), domain_hash), "The transfer is not proposed to you");
+			assert_ok!(NamingServiceModule::accept_transfer(Origin::signed(bob), domain_hash));
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, bob);
+			assert_eq!(NamingServiceModule::domains_of(bob, 0, MAX_PAGE_SIZE), vec![domain_hash]);
+			assert_eq!(NamingServiceModule::pending_transfer(domain_hash), None);
+		});
+	}
+
+	#[test]
+	fn test_cancel_and_expire_transfer() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::propose_transfer(Origin::signed(alice), domain_hash, bob));
+			assert_err!(NamingServiceModule::cancel_transfer(Origin::signed(bob), domain_hash), "You did not propose the transfer");
+			assert_ok!(NamingServiceModule::cancel_transfer(Origin::signed(alice), domain_hash));
+			assert_err!(NamingServiceModule::accept_transfer(Origin::signed(bob), domain_hash), "There is no pending transfer of the domain");
+
+			assert_ok!(NamingServiceModule::propose_transfer(Origin::signed(alice), domain_hash, bob));
+			let expiry = system::Module::<Test>::block_number() + TransferPeriod::get();
+			system::Module::<Test>::set_block_number(expiry);
+			assert_err!(NamingServiceModule::accept_transfer(Origin::signed(bob), domain_hash), "The proposed transfer is expired");
+			NamingServiceModule::on_initialize(expiry);
+			assert_eq!(NamingServiceModule::pending_transfer(domain_hash), None);
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, alice);
+		});
+	}
+
+	#[test]
+	fn test_controller_manages_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(alice), domain_hash, [127, 0, 0, 1]));
+			assert_err!(NamingServiceModule::set_controller(Origin::signed(bob), domain_hash, bob), "You are not the source of the domain");
+			assert_ok!(NamingServiceModule::set_controller(Origin::signed(alice), domain_hash, bob));
+
+			// The controller manages the records but cannot sell the domain
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(bob), domain_hash, [10, 0, 0, 1]));
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(alice), domain_hash, [10, 0, 0, 2]), "You are not the controller or an operator of the domain");
+			assert_err!(NamingServiceModule::transfer_domain(Origin::signed(bob), domain_hash, bob), "You are not the source of the domain");
+
+			// The controller is reset when the domain changes hands
+			assert_ok!(NamingServiceModule::transfer_domain(Origin::signed(alice), domain_hash, charlie));
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(bob), domain_hash, [10, 0, 0, 3]), "You are not the controller or an operator of the domain");
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(charlie), domain_hash, [10, 0, 0, 3]));
+		});
+	}
+
+	#[test]
+	fn test_operator_approvals() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let other_hash = register(alice, b"company.dot");
+
+			assert_ok!(NamingServiceModule::approve(Origin::signed(alice), domain_hash, bob, true));
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(bob), domain_hash, [10, 0, 0, 1]));
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(bob), other_hash, [10, 0, 0, 1]), "You are not the controller or an operator of the domain");
+			assert_ok!(NamingServiceModule::approve(Origin::signed(alice), domain_hash, bob, false));
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(bob), domain_hash, [10, 0, 0, 1]), "You are not the controller or an operator of the domain");
+
+			assert_ok!(NamingServiceModule::set_approval_for_all(Origin::signed(alice), charlie, true));
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(charlie), domain_hash, [10, 0, 0, 2]));
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(charlie), other_hash, [10, 0, 0, 2]));
+			assert_ok!(NamingServiceModule::set_approval_for_all(Origin::signed(alice), charlie, false));
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(charlie), other_hash, [10, 0, 0, 3]), "You are not the controller or an operator of the domain");
+		});
+	}
+
+	#[test]
+	fn test_text_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let reserved = Balances::reserved_balance(&alice);
+			assert_err!(NamingServiceModule::set_text(Origin::signed(bob), domain_hash, b"email".to_vec(), b"bob@example.com".to_vec()), "You are not the controller or an operator of the domain");
+			assert_err!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, vec![b'k'; 17], vec![]), "The key of the text record is too long");
+			assert_err!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, b"url".to_vec(), vec![b'v'; 65]), "The value of the text record is too long");
+
+			assert_ok!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, b"email".to_vec(), b"a@b.c".to_vec()));
+			assert_eq!(NamingServiceModule::text(&domain_hash, &b"email".to_vec()).unwrap().value, b"a@b.c".to_vec());
+			assert_eq!(Balances::reserved_balance(&alice), reserved + 10 * TextByteDeposit::get());
+
+			// Updating the record replaces its deposit
+			assert_ok!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, b"email".to_vec(), b"alice@b.c".to_vec()));
+			assert_eq!(Balances::reserved_balance(&alice), reserved + 14 * TextByteDeposit::get());
+			assert_eq!(NamingServiceModule::text_keys(domain_hash), vec![b"email".to_vec()]);
+
+			assert_eq!(NamingServiceModule::resolve_texts(domain_hash), vec![(b"email".to_vec(), b"alice@b.c".to_vec())]);
+
+			assert_ok!(NamingServiceModule::clear_text(Origin::signed(alice), domain_hash, b"email".to_vec()));
+			assert_eq!(NamingServiceModule::text(&domain_hash, &b"email".to_vec()), None);
+			assert!(NamingServiceModule::text_keys(domain_hash).is_empty());
+			assert_eq!(Balances::reserved_balance(&alice), reserved);
+			assert_err!(NamingServiceModule::clear_text(Origin::signed(alice), domain_hash, b"email".to_vec()), "The text record does not exist");
+		});
+	}
+
+	#[test]
+	fn test_text_records_released_with_domain() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, b"url".to_vec(), b"https://a.b".to_vec()));
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::text(&domain_hash, &b"url".to_vec()), None);
+			assert_eq!(Balances::reserved_balance(&alice), 0);
+		});
+	}
+
+	#[test]
+	fn test_text_records_of_revoked_subdomains_are_released() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, charlie) = (1u64, 3u64);
+			let parent_hash = register(alice, b"company.dot");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"api".to_vec(), charlie));
+			let domain_hash = NamingServiceModule::domain_hash_of(b"api.company.dot");
+			assert_ok!(NamingServiceModule::set_text(Origin::signed(charlie), domain_hash, b"url".to_vec(), b"https://a.b".to_vec()));
+			assert!(Balances::reserved_balance(&charlie) > 0);
+
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), parent_hash));
+			assert_eq!(NamingServiceModule::text(&domain_hash, &b"url".to_vec()), None);
+			assert!(NamingServiceModule::text_keys(domain_hash).is_empty());
+			assert_eq!(Balances::reserved_balance(&charlie), 0);
+		});
+	}
+
+	#[test]
+	fn test_ip_addresses() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(alice), domain_hash, [127, 0, 0, 1]));
+			assert_ok!(NamingServiceModule::add_ipv4(Origin::signed(alice), domain_hash, [10, 0, 0, 1]));
+			assert_err!(NamingServiceModule::add_ipv4(Origin::signed(alice), domain_hash, [10, 0, 0, 1]), "The domain already has the address");
+			assert_err!(NamingServiceModule::add_ipv4(Origin::signed(alice), domain_hash, [10, 0, 0, 2]), "The domain has too many addresses");
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[127, 0, 0, 1], [10, 0, 0, 1]]);
+			assert_ok!(NamingServiceModule::remove_ipv4(Origin::signed(alice), domain_hash, [127, 0, 0, 1]));
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[10, 0, 0, 1]]);
+
+			// 2001:db8::1
+			let mut ipv6 = [0u8; 16];
+			ipv6[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+			ipv6[15] = 1;
+			assert_ok!(NamingServiceModule::set_ipv6(Origin::signed(alice), domain_hash, ipv6));
+			assert_ok!(NamingServiceModule::add_ipv6(Origin::signed(alice), domain_hash, [0u8; 16]));
+			assert_ok!(NamingServiceModule::remove_ipv6(Origin::signed(alice), domain_hash, [0u8; 16]));
+			assert_err!(NamingServiceModule::remove_ipv6(Origin::signed(alice), domain_hash, [0u8; 16]), "The domain does not have the address");
+			assert_eq!(NamingServiceModule::ipv6(domain_hash), vec![ipv6]);
+
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), domain_hash));
+			assert!(NamingServiceModule::ipv4(domain_hash).is_empty());
+			assert!(NamingServiceModule::ipv6(domain_hash).is_empty());
+		});
+	}
+
+	// Domain in the layout of the domains registered before `LegacyDomain` was replaced
+	fn legacy_domain(source: u64, name: &[u8], ipv4: IPV4) -> LegacyDomain<u64, u128, u64> {
+		LegacyDomain {
+			name: name.to_vec(),
+			source,
+			price: 1000,
+			ttl: RegistrationPeriod::get(),
+			registered_date: system::Module::<Test>::block_number(),
+			available: false,
+			highest_bid: 0,
+			bidder: source,
+			auction_closed: 0,
+			ipv4,
+			ipv6: [0u16; 6],
+		}
+	}
+
+	// Store the domain in the legacy layout
+	fn store_legacy_domain(source: u64, name: &[u8], ipv4: IPV4) -> H256 {
+		store_legacy(legacy_domain(source, name, ipv4))
+	}
+
+	fn store_legacy(legacy: LegacyDomain<u64, u128, u64>) -> H256 {
+		let domain_hash = NamingServiceModule::domain_hash_of(&legacy.name);
+		unhashed::put_raw(&<Resolver<Test>>::hashed_key_for(domain_hash), &legacy.encode());
+		domain_hash
+	}
+
+	#[test]
+	fn test_migrate_domains() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = store_legacy_domain(alice, b"hyungsukkang.dot", [127, 0, 0, 1]);
+
+			assert_ok!(NamingServiceModule::migrate_domains(Origin::ROOT, vec![domain_hash, H256::zero()]));
+			let key = <Resolver<Test>>::hashed_key_for(domain_hash);
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(unhashed::get_raw(&key), Some(domain.encode()));
+			assert_eq!(domain.name, b"hyungsukkang.dot".to_vec());
+			assert_eq!(domain.source, alice);
+			assert_eq!(domain.controller, alice);
+			assert_eq!(domain.price, 1000);
+			assert_eq!(domain.deposit, 0);
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[127, 0, 0, 1]]);
+			assert_eq!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE), vec![domain_hash]);
+
+			// Migrating again changes nothing
+			assert_ok!(NamingServiceModule::migrate_domains(Origin::ROOT, vec![domain_hash]));
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[127, 0, 0, 1]]);
+		});
+	}
+
+	#[test]
+	fn test_legacy_domain_migrates_on_first_read() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = store_legacy_domain(alice, b"hyungsukkang.dot", [127, 0, 0, 1]);
+
+			// A write which is not about the addresses keeps the legacy address
+			assert_ok!(NamingServiceModule::set_controller(Origin::signed(alice), domain_hash, bob));
+			assert_eq!(NamingServiceModule::domain(domain_hash).controller, bob);
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[127, 0, 0, 1]]);
+
+			assert_ok!(NamingServiceModule::renew(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::ipv4(domain_hash), vec![[127, 0, 0, 1]]);
+			assert_eq!(NamingServiceModule::domain(domain_hash).ttl, 2 * RegistrationPeriod::get());
+		});
+	}
+
+	#[test]
+	fn test_legacy_auction_is_closed_on_migration() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			// Bob holds a reserve for a domain of his own, which the legacy bid was never part of
+			register(bob, b"company.dot");
+			let reserved = Balances::reserved_balance(&bob);
+			let mut legacy = legacy_domain(alice, b"hyungsukkang.dot", [0; 4]);
+			legacy.available = true;
+			legacy.highest_bid = reserved;
+			legacy.bidder = bob;
+			legacy.auction_closed = system::Module::<Test>::block_number() + AuctionPeriod::get();
+			let domain_hash = store_legacy(legacy);
+
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert!(!domain.available);
+			assert_eq!(domain.bidder, alice);
+			assert_eq!(domain.highest_bid, 0);
+			assert!(NamingServiceModule::auction_status(domain_hash).is_none());
+			assert_err!(NamingServiceModule::new_bid(Origin::signed(charlie), domain_hash, 1000), "The auction for the domain is currently not available");
+
+			// A new auction does not touch the reserve of bob
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(charlie), domain_hash, 1000));
+			system::Module::<Test>::set_block_number(NamingServiceModule::domain(domain_hash).auction_closed + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(charlie), domain_hash));
+			assert_eq!(NamingServiceModule::domain(domain_hash).source, charlie);
+			assert_eq!(Balances::reserved_balance(&bob), reserved);
+		});
+	}
+
+	fn hex(s: &str) -> Vec<u8> {
+		(0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+	}
+
+	#[test]
+	fn test_address_validation() {
+		assert_eq!(address::to_raw(address::DOT, &[1u8; 32]), Ok(vec![1u8; 32]));
+		assert_err!(address::to_raw(address::KSM, &[1u8; 20]), "The address of an SS58 chain must be a 32 byte account id");
+		assert_eq!(address::to_raw(address::ETH, &[1u8; 20]), Ok(vec![1u8; 20]));
+		assert_err!(address::to_raw(address::ETC, &[1u8; 32]), "The address of an EVM chain must be 20 bytes");
+		assert_eq!(address::to_raw(9999, b"anything"), Ok(b"anything".to_vec()));
+		assert_err!(address::to_raw(9999, &[]), "The address is empty");
+	}
+
+	#[test]
+	fn test_bitcoin_addresses_are_stored_as_scripts() {
+		// segwit version 0, and taproot with a bech32m checksum
+		let p2wpkh = hex("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+		assert_eq!(address::to_raw(address::BTC, b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"), Ok(p2wpkh.clone()));
+		assert_eq!(address::to_raw(address::BTC, b"bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"), Ok(hex("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262")));
+		assert_eq!(address::to_raw(address::BTC, b"bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0"), Ok(hex("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")));
+		assert_err!(address::to_raw(address::BTC, b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"), "The checksum of the address is invalid");
+		assert_err!(address::to_raw(address::BTC, b"tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"), "The address is for another chain");
+		// A taproot address with a bech32 checksum instead of bech32m
+		assert_err!(address::to_raw(address::BTC, b"bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd"), "The checksum of the address is invalid");
+
+		// base58check P2PKH and P2SH
+		assert_eq!(address::to_raw(address::BTC, b"1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"), Ok(hex("76a91477bff20c60e522dfaa3350c39b030a5d004e839a88ac")));
+		assert_eq!(address::to_raw(address::BTC, b"3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"), Ok(hex("a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87")));
+		assert_err!(address::to_raw(address::BTC, b"1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3"), "The checksum of the address is invalid");
+		assert_err!(address::to_raw(address::BTC, b"LaMT348PWRnrqeeWArpwQPbuanpXDZGEUz"), "The address is for another chain");
+		assert_eq!(address::to_raw(address::LTC, b"LaMT348PWRnrqeeWArpwQPbuanpXDZGEUz"), Ok(hex("76a914a5f4d12ce3685781b227c1f39548ddef429e978388ac")));
+		assert_eq!(address::to_raw(address::LTC, b"MQMcJhpWHYVeQArcZR3sBgyPZxxRtnH441"), Ok(hex("a914b48297bff5dadecc5f36145cec6a5f20d57c8f9b87")));
+
+		// Standard scripts are stored as they are
+		assert_eq!(address::to_raw(address::BTC, &p2wpkh), Ok(p2wpkh.clone()));
+		assert_err!(address::to_raw(address::BTC, &p2wpkh[..21]), "The address is not bech32");
+	}
+
+	#[test]
+	fn test_address_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_err!(NamingServiceModule::set_address(Origin::signed(bob), domain_hash, address::ETH, vec![1u8; 20]), "You are not the controller or an operator of the domain");
+			assert_err!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::ETH, vec![1u8; 32]), "The address of an EVM chain must be 20 bytes");
+			assert_ok!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::ETH, vec![1u8; 20]));
+			assert_ok!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::DOT, vec![2u8; 32]));
+			assert_err!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::KSM, vec![3u8; 32]), "The domain has too many addresses");
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::ETH), Some(vec![1u8; 20]));
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::KSM), None);
+
+			assert_ok!(NamingServiceModule::clear_address(Origin::signed(alice), domain_hash, address::ETH));
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::ETH), None);
+			assert_err!(NamingServiceModule::clear_address(Origin::signed(alice), domain_hash, address::ETH), "The domain has no address for the coin type");
+			assert_eq!(NamingServiceModule::coin_types(domain_hash), vec![address::DOT]);
+
+			// Bitcoin addresses resolve to their scriptPubkey
+			assert_ok!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::BTC, b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_vec()));
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::BTC), Some(hex("0014751e76e8199196d454941c45d1b3a323f1433bd6")));
+			assert_ok!(NamingServiceModule::clear_address(Origin::signed(alice), domain_hash, address::BTC));
+
+			// Addresses stop resolving once the domain is past its grace period
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			system::Module::<Test>::set_block_number(expiry);
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::DOT), Some(vec![2u8; 32]));
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get());
+			assert_eq!(NamingServiceModule::resolve_address(domain_hash, address::DOT), None);
+		});
+	}
+
+	fn ipfs_content_hash() -> Vec<u8> {
+		// ipfs-ns, CIDv1, dag-pb, sha2-256 multihash
+		let mut hash = vec![0xe3, 0x01, 0x01, 0x70, 0x12, 0x20];
+		hash.extend_from_slice(&[7u8; 32]);
+		hash
+	}
+
+	#[test]
+	fn test_content_hash_validation() {
+		assert_ok!(content_hash::validate(&ipfs_content_hash()));
+		// CIDv0
+		let mut cid_v0 = vec![0xe3, 0x01, 0x12, 0x20];
+		cid_v0.extend_from_slice(&[7u8; 32]);
+		assert_ok!(content_hash::validate(&cid_v0));
+		cid_v0.pop();
+		assert_err!(content_hash::validate(&cid_v0), "The digest of the content hash has a wrong length");
+
+		let mut swarm = vec![0xe4, 0x01, 0x01, 0xfa, 0x01, 0x1b, 0x20];
+		swarm.extend_from_slice(&[7u8; 32]);
+		assert_ok!(content_hash::validate(&swarm));
+		swarm[3] = 0x70;
+		assert_err!(content_hash::validate(&swarm), "The Swarm content hash is not a manifest");
+
+		let mut arweave = vec![0x90, 0xb2, 0xca, 0x05];
+		arweave.extend_from_slice(&[7u8; 32]);
+		assert_ok!(content_hash::validate(&arweave));
+		arweave.pop();
+		assert_err!(content_hash::validate(&arweave), "The Arweave content hash must be a 32 byte transaction id");
+
+		assert_err!(content_hash::validate(&[0x55, 0x01]), "The namespace of the content hash is not supported");
+		assert_err!(content_hash::validate(&[0xe3]), "The content hash is not a valid multiformat");
+	}
+
+	#[test]
+	fn test_content_hash_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_err!(NamingServiceModule::set_content_hash(Origin::signed(bob), domain_hash, ipfs_content_hash()), "You are not the controller or an operator of the domain");
+			assert_err!(NamingServiceModule::set_content_hash(Origin::signed(alice), domain_hash, vec![0xe3, 0x01]), "The content hash is not a valid multiformat");
+			assert_ok!(NamingServiceModule::set_content_hash(Origin::signed(alice), domain_hash, ipfs_content_hash()));
+			assert_eq!(NamingServiceModule::resolve_content_hash(domain_hash), Some(ipfs_content_hash()));
+			assert_eq!(NamingServiceModule::domain_info(domain_hash).unwrap().content_hash, Some(ipfs_content_hash()));
+
+			// The content hash stops resolving once the domain is past its grace period
+			let now = system::Module::<Test>::block_number();
+			system::Module::<Test>::set_block_number(NamingServiceModule::expires_at(domain_hash) + GracePeriod::get());
+			assert_eq!(NamingServiceModule::resolve_content_hash(domain_hash), None);
+			assert_eq!(NamingServiceModule::domain_info(domain_hash).unwrap().content_hash, None);
+			system::Module::<Test>::set_block_number(now);
+
+			assert_ok!(NamingServiceModule::clear_content_hash(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::resolve_content_hash(domain_hash), None);
+			assert_err!(NamingServiceModule::clear_content_hash(Origin::signed(alice), domain_hash), "The domain has no content hash");
+		});
+	}
+
+	#[test]
+	fn test_primary_name() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_err!(NamingServiceModule::set_primary_name(Origin::signed(bob), domain_hash), "You are not the source or the controller of the domain");
+			assert_ok!(NamingServiceModule::set_primary_name(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::primary_name(alice), Some(domain_hash));
+
+			// The controller can display the name as well, until it is replaced
+			assert_ok!(NamingServiceModule::set_controller(Origin::signed(alice), domain_hash, bob));
+			assert_ok!(NamingServiceModule::set_primary_name(Origin::signed(bob), domain_hash));
+			assert_eq!(NamingServiceModule::primary_name(bob), Some(domain_hash));
+			assert_ok!(NamingServiceModule::set_controller(Origin::signed(alice), domain_hash, charlie));
+			assert_eq!(NamingServiceModule::primary_name(bob), None);
+
+			// A transfer unsets the primary name of the prior owner
+			assert_ok!(NamingServiceModule::transfer_domain(Origin::signed(alice), domain_hash, charlie));
+			assert_eq!(NamingServiceModule::primary_name(alice), None);
+			assert_eq!(NamingServiceModule::primary(alice), None);
+		});
+	}
+
+	#[test]
+	fn test_primary_name_expires() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::set_primary_name(Origin::signed(alice), domain_hash));
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get());
+			assert_eq!(NamingServiceModule::primary_name(alice), None);
+			assert_err!(NamingServiceModule::set_primary_name(Origin::signed(alice), domain_hash), "The domain does not exist or is expired");
+
+			assert_ok!(NamingServiceModule::clear_primary_name(Origin::signed(alice)));
+			assert_err!(NamingServiceModule::clear_primary_name(Origin::signed(alice)), "You have no primary name");
+		});
+	}
+
+	#[test]
+	fn test_owner_index() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let first = register(alice, b"first.dot");
+			let second = register(alice, b"second.dot");
+			let third = register(alice, b"third.dot");
+			assert_eq!(NamingServiceModule::owned_count(alice), 3);
+			assert_eq!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE), vec![first, second, third]);
+			assert_eq!(NamingServiceModule::domains_of(alice, 1, 1), vec![second]);
+			assert!(NamingServiceModule::domains_of(alice, 3, 1).is_empty());
+
+			// The last domain takes the place of the removed one
+			assert_ok!(NamingServiceModule::transfer_domain(Origin::signed(alice), first, bob));
+			assert_eq!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE), vec![third, second]);
+			assert_eq!(NamingServiceModule::owned_index(&alice, &third), Some(0));
+			assert_eq!(NamingServiceModule::owned_index(&alice, &first), None);
+			assert_eq!(NamingServiceModule::domains_of(bob, 0, MAX_PAGE_SIZE), vec![first]);
+
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), second));
+			assert_eq!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE), vec![third]);
+			assert_ok!(NamingServiceModule::reverse_resolve(Origin::signed(bob), alice, 0, 10));
+		});
+	}
+
+	#[test]
+	fn test_owner_index_migration() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			// Drop the index as if the domain was registered before it existed
+			<OwnedIndex<Test>>::remove(&alice, &domain_hash);
+			<OwnedDomains<Test>>::remove(&alice, &0);
+			<OwnedCount<Test>>::remove(alice);
+			<Reverse<Test>>::insert(alice, vec![domain_hash]);
+
+			assert_ok!(NamingServiceModule::migrate_domains(Origin::ROOT, vec![domain_hash]));
+			assert_eq!(NamingServiceModule::domains_of(alice, 0, MAX_PAGE_SIZE), vec![domain_hash]);
+			assert!(!<Reverse<Test>>::exists(alice));
+
+			assert_ok!(NamingServiceModule::migrate_domains(Origin::ROOT, vec![domain_hash]));
+			assert_eq!(NamingServiceModule::owned_count(alice), 1);
+		});
+	}
+
+	#[test]
+	fn test_domain_info_and_auction_status() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			assert!(NamingServiceModule::domain_info(H256::zero()).is_none());
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(alice), domain_hash, [127, 0, 0, 1]));
+			assert_ok!(NamingServiceModule::set_address(Origin::signed(alice), domain_hash, address::ETH, vec![1u8; 20]));
+
+			let info = NamingServiceModule::domain_info(domain_hash).unwrap();
+			assert_eq!(info.hash, domain_hash);
+			assert_eq!(info.name, b"hyungsukkang.dot".to_vec());
+			assert_eq!(info.source, alice);
+			assert_eq!(info.controller, alice);
+			assert_eq!(info.expires_at, NamingServiceModule::expires_at(domain_hash));
+			assert_eq!(info.state, DomainState::Active);
+			assert_eq!(info.ipv4, vec![[127, 0, 0, 1]]);
+			assert_eq!(info.addresses, vec![(address::ETH, vec![1u8; 20])]);
+			assert!(!info.in_auction);
+			assert!(NamingServiceModule::auction_status(domain_hash).is_none());
+
+			// The records are hidden once the domain stops resolving
+			let now = system::Module::<Test>::block_number();
+			system::Module::<Test>::set_block_number(NamingServiceModule::expires_at(domain_hash) + GracePeriod::get());
+			let info = NamingServiceModule::domain_info(domain_hash).unwrap();
+			assert_eq!(info.state, DomainState::Premium);
+			assert!(info.ipv4.is_empty());
+			assert!(info.addresses.is_empty());
+			system::Module::<Test>::set_block_number(now);
+
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::Candle));
+			let status = NamingServiceModule::auction_status(domain_hash).unwrap();
+			let closes_at = system::Module::<Test>::block_number() + AuctionPeriod::get();
+			assert_eq!(status.mode, AuctionMode::Candle);
+			assert_eq!(status.bidder, alice);
+			assert_eq!(status.closes_at, closes_at);
+			assert_eq!(status.ending_start, Some(closes_at - EndingPeriod::get()));
+		});
+	}
+
+	#[test]
+	fn test_dns_wire_format() {
+		let records = vec![
+			dns::RecordData::Cname(b"hyungsukkang.dot".to_vec()),
+			dns::RecordData::Mx { preference: 10, exchange: b"mail.dot".to_vec() },
+			dns::RecordData::Mx { preference: 0, exchange: vec![] },
+			dns::RecordData::Srv { priority: 1, weight: 5, port: 443, target: b"www.dot".to_vec() },
+			dns::RecordData::Txt(vec![b"v=spf1 -all".to_vec(), vec![]]),
+			dns::RecordData::Tlsa { usage: 3, selector: 1, matching_type: 1, data: vec![7u8; 32] },
+		];
+		for record in records {
+			let rdata = record.to_wire();
+			assert_ok!(dns::validate(record.rtype(), &rdata));
+			assert_eq!(dns::RecordData::from_wire(record.rtype(), &rdata), Ok(record));
+		}
+		assert_eq!(dns::RecordData::Mx { preference: 10, exchange: b"mail.dot".to_vec() }.to_wire(), b"\x00\x0a\x04mail\x03dot\x00".to_vec());
+
+		assert_err!(dns::validate(dns::TYPE_A, &[127, 0, 0, 1]), "A and AAAA records are set with the IPV4 and IPV6 calls");
+		assert_err!(dns::validate(99, &[]), "The DNS record type is not supported");
+		assert_err!(dns::validate(dns::TYPE_MX, b"\x00\x0a\x04mail\x03dot"), "The DNS record is truncated");
+		assert_err!(dns::validate(dns::TYPE_MX, b"\x00\x0a\x04mail\x03dot\x00\x00"), "The DNS record has trailing bytes");
+		assert_err!(dns::validate(dns::TYPE_CNAME, b"\xc0\x0c"), "Names in DNS records cannot be compressed");
+		assert_err!(dns::validate(dns::TYPE_CNAME, b"\x04MAIL\x03dot\x00"), "The domain name is not normalized");
+		assert_err!(dns::validate(dns::TYPE_CNAME, b"\x00"), "The CNAME target is empty");
+		assert_err!(dns::validate(dns::TYPE_TLSA, &[3, 1, 1, 7, 7]), "The TLSA digest has a wrong length");
+		assert_err!(dns::validate(dns::TYPE_TXT, &[0u8; 513]), "The DNS record is too long");
+	}
+
+	#[test]
+	fn test_dns_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let reserved = Balances::reserved_balance(&alice);
+			let mx = dns::RecordData::Mx { preference: 10, exchange: b"mail.dot".to_vec() }.to_wire();
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(bob), domain_hash, dns::TYPE_MX, 3600, vec![mx.clone()]), "You are not the controller or an operator of the domain");
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_MX, 3600, vec![]), "The record set is empty");
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_MX, 3600, vec![mx.clone(); 17]), "The record set has too many records");
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_AAAA, 3600, vec![vec![0u8; 16]]), "A and AAAA records are set with the IPV4 and IPV6 calls");
+
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_MX, 3600, vec![mx.clone()]));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_MX), Some((3600, vec![mx.clone()])));
+			assert_eq!(Balances::reserved_balance(&alice), reserved + 12 * TextByteDeposit::get());
+			assert_eq!(NamingServiceModule::dns_record_types(domain_hash), vec![dns::TYPE_MX]);
+
+			// A and AAAA records come from the IP addresses, TXT records from the text records
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_A), None);
+			assert_ok!(NamingServiceModule::set_ipv4(Origin::signed(alice), domain_hash, [127, 0, 0, 1]));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_A), Some((dns::DEFAULT_TTL, vec![vec![127, 0, 0, 1]])));
+			assert_err!(NamingServiceModule::set_address_ttl(Origin::signed(alice), domain_hash, dns::TYPE_MX, 60), "The DNS type is not A or AAAA");
+			assert_ok!(NamingServiceModule::set_address_ttl(Origin::signed(alice), domain_hash, dns::TYPE_A, 60));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_A), Some((60, vec![vec![127, 0, 0, 1]])));
+			assert_ok!(NamingServiceModule::set_text(Origin::signed(alice), domain_hash, b"email".to_vec(), b"a@b.c".to_vec()));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_TXT), Some((dns::DEFAULT_TTL, vec![b"\x0bemail=a@b.c".to_vec()])));
+			let txt = dns::RecordData::Txt(vec![b"v=spf1 -all".to_vec()]).to_wire();
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_TXT, 600, vec![txt.clone()]));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_TXT), Some((600, vec![txt])));
+
+			assert_ok!(NamingServiceModule::clear_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_MX));
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_MX), None);
+			assert_err!(NamingServiceModule::clear_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_MX), "The domain has no DNS records of the type");
+			assert_eq!(NamingServiceModule::dns_record_types(domain_hash), vec![dns::TYPE_TXT]);
+
+			// Records stop resolving once the domain is past its grace period
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get());
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_TXT), None);
+			assert_eq!(NamingServiceModule::resolve_dns(domain_hash, dns::TYPE_A), None);
+		});
+	}
+
+	#[test]
+	fn test_dns_records_released_with_domain() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let txt = dns::RecordData::Txt(vec![b"v=spf1 -all".to_vec()]).to_wire();
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), domain_hash, dns::TYPE_TXT, 600, vec![txt]));
+			assert_ok!(NamingServiceModule::release_domain(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::dns_records(&domain_hash, &dns::TYPE_TXT), None);
+			assert!(NamingServiceModule::dns_record_types(domain_hash).is_empty());
+			assert_eq!(Balances::reserved_balance(&alice), 0);
+		});
+	}
+
+	#[test]
+	fn test_service_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let parent_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), parent_hash, b"_tcp".to_vec(), alice));
+			let tcp = NamingServiceModule::domain_hash_of(b"_tcp.hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), tcp, b"_sip".to_vec(), alice));
+			assert_ok!(NamingServiceModule::create_subdomain(Origin::signed(alice), tcp, b"_443".to_vec(), alice));
+			assert_err!(NamingServiceModule::create_subdomain(Origin::signed(alice), tcp, b"www".to_vec(), alice), "The domain name is not normalized");
+
+			let sip = NamingServiceModule::domain_hash_of(b"_sip._tcp.hyungsukkang.dot");
+			let srv = dns::RecordData::Srv { priority: 10, weight: 5, port: 5060, target: b"sip.hyungsukkang.dot".to_vec() }.to_wire();
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), sip, dns::TYPE_SRV, 600, vec![srv.clone()]));
+			assert_eq!(NamingServiceModule::resolve_dns(sip, dns::TYPE_SRV), Some((600, vec![srv])));
+
+			let https = NamingServiceModule::domain_hash_of(b"_443._tcp.hyungsukkang.dot");
+			let tlsa = dns::RecordData::Tlsa { usage: 3, selector: 1, matching_type: 1, data: vec![7u8; 32] }.to_wire();
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), https, dns::TYPE_TLSA, 600, vec![tlsa.clone()]));
+			assert_eq!(NamingServiceModule::resolve_dns(https, dns::TYPE_TLSA), Some((600, vec![tlsa])));
+
+			// Service names can be the targets of aliases, e.g. for delegated ACME challenges
+			let cname = dns::RecordData::Cname(b"_acme-challenge.hyungsukkang.dot".to_vec()).to_wire();
+			assert_ok!(dns::validate(dns::TYPE_CNAME, &cname));
+		});
+	}
+
+	#[test]
+	fn test_cname_records() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let first = register(alice, b"first.dot");
+			let second = register(alice, b"second.dot");
+			let third = register(alice, b"third.dot");
+			let cname = |target: &[u8]| vec![dns::RecordData::Cname(target.to_vec()).to_wire()];
+
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_CNAME, 60, vec![cname(b"second.dot")[0].clone(); 2]), "A domain has a single CNAME record");
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_CNAME, 60, cname(b"first.dot")), "The CNAME would make a cycle");
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_CNAME, 60, cname(b"second.dot")));
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), second, dns::TYPE_CNAME, 60, cname(b"third.dot")));
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), third, dns::TYPE_CNAME, 60, cname(b"first.dot")), "The CNAME would make a cycle");
+			// Targets off the chain are not followed
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), third, dns::TYPE_CNAME, 60, cname(b"example.com")));
+			assert_eq!(NamingServiceModule::resolve_dns(first, dns::TYPE_CNAME), Some((60, cname(b"second.dot"))));
+
+			// A CNAME cannot be mixed with other records
+			let txt = dns::RecordData::Txt(vec![b"hello".to_vec()]).to_wire();
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_TXT, 60, vec![txt.clone()]), "The domain is an alias with a CNAME record");
+			assert_err!(NamingServiceModule::set_ipv4(Origin::signed(alice), first, [127, 0, 0, 1]), "The domain is an alias with a CNAME record");
+			assert_ok!(NamingServiceModule::clear_dns_records(Origin::signed(alice), first, dns::TYPE_CNAME));
+			assert_ok!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_TXT, 60, vec![txt]));
+			assert_err!(NamingServiceModule::set_dns_records(Origin::signed(alice), first, dns::TYPE_CNAME, 60, cname(b"second.dot")), "The domain has other DNS records than the CNAME");
+		});
+	}
+
+	#[test]
+	fn test_deposit_changes_hands_through_auction() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let deposit = NamingServiceModule::domain(domain_hash).deposit;
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 1000));
+			system::Module::<Test>::set_block_number(NamingServiceModule::domain(domain_hash).auction_closed + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash));
+
+			assert_eq!(Balances::reserved_balance(&alice), 0);
+			assert_eq!(Balances::free_balance(&alice), 1_000_000_000_000 + 1000);
+			assert_eq!(Balances::reserved_balance(&bob), deposit);
+		});
+	}
+
+	#[test]
+	fn test_bids_are_escrowed() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert_err!(NamingServiceModule::new_bid(Origin::signed(alice), domain_hash, 1000), "The source cannot bid on its own domain");
+
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 1000));
+			assert_eq!(Balances::reserved_balance(&bob), 1000);
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 1500));
+			assert_eq!(Balances::reserved_balance(&bob), 1500);
+
+			// Bob is refunded as soon as he is outbid
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(charlie), domain_hash, 2000));
+			assert_eq!(Balances::reserved_balance(&bob), 0);
+			assert_eq!(Balances::reserved_balance(&charlie), 2000);
+			assert_err!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 10_000_000_000_000), "not enough free funds");
+		});
+	}
+
+	#[test]
+	fn test_failed_sale_leaves_domain_with_source() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			// Bob escrows all of his free balance, so he cannot reserve the deposit of the domain
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 1_000_000_000_000));
+			system::Module::<Test>::set_block_number(NamingServiceModule::domain(domain_hash).auction_closed + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash));
+
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(domain.source, alice);
+			assert!(!domain.available);
+			assert_eq!(Balances::reserved_balance(&bob), 0);
+			assert_eq!(Balances::free_balance(&bob), 1_000_000_000_000);
+			assert_eq!(Balances::free_balance(&alice), 1_000_000_000_000 - domain.deposit);
+		});
+	}
+
+	#[test]
+	fn test_length_pricing() {
+		with_externalities(&mut new_test_ext(), || {
+			let price = |name: &[u8]| <LengthPricing<Test> as PriceOracle<u128>>::price(name);
+			assert_eq!(price(b"abc.dot"), 1000 * BasePrice::get());
+			assert_eq!(price(b"abcd.dot"), 100 * BasePrice::get());
+			assert_eq!(price(b"abcde.dot"), BasePrice::get());
+			assert_eq!(price("\u{d55c}\u{ae00}.dot".as_bytes()), 1000 * BasePrice::get());
+
+			assert_err!(NamingServiceModule::set_premium_price(Origin::signed(1), b"hyungsukkang.dot".to_vec(), Some(42)), "bad origin: expected to be a root origin");
+			assert_ok!(NamingServiceModule::set_premium_price(Origin::ROOT, b"hyungsukkang.dot".to_vec(), Some(42)));
+			assert_eq!(price(b"hyungsukkang.dot"), 42);
+			let domain_hash = register(1, b"hyungsukkang.dot");
+			assert_eq!(Balances::reserved_balance(&1), 42);
+
+			// Renewal tops up the deposit to the current price
+			assert_ok!(NamingServiceModule::set_premium_price(Origin::ROOT, b"hyungsukkang.dot".to_vec(), Some(100)));
+			assert_ok!(NamingServiceModule::renew(Origin::signed(1), domain_hash));
+			assert_eq!(Balances::reserved_balance(&1), 100);
 		});
 	}
 
 	// TODO: Test other functions with features
 	// - Catching events after the event
-	// - Set balance of the test account
+
+	#[test]
+	fn test_sealed_bid_auction() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie, dave) = (1u64, 2u64, 3u64, 4u64);
+			let initial = 1_000_000_000_000;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let alice_free = Balances::free_balance(&alice);
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::SealedBid));
+			assert_err!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 5000), "Bids of a sealed-bid auction are made with seal_bid");
+
+			let salt = H256([9; 32]);
+			let seal = |bid: u128| BlakeTwo256::hash_of(&(bid, salt));
+			assert_ok!(NamingServiceModule::seal_bid(Origin::signed(bob), domain_hash, seal(5000), 6000));
+			assert_ok!(NamingServiceModule::seal_bid(Origin::signed(charlie), domain_hash, seal(3000), 3000));
+			assert_ok!(NamingServiceModule::seal_bid(Origin::signed(dave), domain_hash, seal(8000), 8000));
+			assert_eq!(Balances::reserved_balance(&bob), 6000);
+
+			// Bids are revealed only after the auction is closed
+			assert_err!(NamingServiceModule::reveal_bid(Origin::signed(bob), domain_hash, 5000, salt), "The sealed bids are not being revealed");
+			let auction_closed = NamingServiceModule::domain(domain_hash).auction_closed;
+			system::Module::<Test>::set_block_number(auction_closed);
+			assert_err!(NamingServiceModule::reveal_bid(Origin::signed(bob), domain_hash, 6000, salt), "The bid does not match the sealed bid");
+			assert_ok!(NamingServiceModule::reveal_bid(Origin::signed(bob), domain_hash, 5000, salt));
+			assert_ok!(NamingServiceModule::reveal_bid(Origin::signed(charlie), domain_hash, 3000, salt));
+			assert_err!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash), "The auction has not been finalized yet");
+
+			// Dave never reveals his bid
+			system::Module::<Test>::set_block_number(auction_closed + RevealPeriod::get() + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash));
+
+			// Bob wins and pays the second highest bid
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(domain.source, bob);
+			assert_eq!(domain.price, 3000);
+			assert_eq!(Balances::free_balance(&alice), alice_free + domain.deposit + 3000);
+			assert_eq!(Balances::free_balance(&bob), initial - 3000 - domain.deposit);
+			assert_eq!(Balances::free_balance(&charlie), initial);
+			assert_eq!(Balances::free_balance(&dave), initial - 4000);
+			assert_eq!(Balances::reserved_balance(&dave), 0);
+			assert!(NamingServiceModule::sealed_bidders(domain_hash).is_empty());
+		});
+	}
+
+	#[test]
+	fn test_sealed_bid_below_reserve_price_does_not_sell() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let initial = 1_000_000_000_000;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let reserve = NamingServiceModule::registration_deposit(b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::SealedBid));
+
+			let salt = H256([9; 32]);
+			assert_ok!(NamingServiceModule::seal_bid(Origin::signed(bob), domain_hash, BlakeTwo256::hash_of(&(reserve - 1, salt)), reserve));
+			let auction_closed = NamingServiceModule::domain(domain_hash).auction_closed;
+			system::Module::<Test>::set_block_number(auction_closed);
+			assert_ok!(NamingServiceModule::reveal_bid(Origin::signed(bob), domain_hash, reserve - 1, salt));
+
+			system::Module::<Test>::set_block_number(auction_closed + RevealPeriod::get() + 1);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash));
+
+			// The only bid is below the registration price, so the domain stays with alice and bob is refunded
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(domain.source, alice);
+			assert!(!domain.available);
+			assert_eq!(Balances::free_balance(&bob), initial);
+			assert_eq!(Balances::reserved_balance(&bob), 0);
+		});
+	}
+
+	#[test]
+	fn test_candle_auction() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+			let initial = 1_000_000_000_000;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::Candle));
+			let auction_closed = NamingServiceModule::domain(domain_hash).auction_closed;
+			let ending_start = auction_closed - EndingPeriod::get();
+
+			// Bob's bid before the ending period is the leading bid at its first block
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(bob), domain_hash, 1000));
+			assert_eq!(NamingServiceModule::candle_bid(&domain_hash, &ending_start), Some((bob, 1000)));
+			system::Module::<Test>::set_block_number(ending_start + 2);
+			assert_ok!(NamingServiceModule::new_bid(Origin::signed(charlie), domain_hash, 2000));
+			assert_eq!(NamingServiceModule::candle_bid(&domain_hash, &(ending_start + 2)), Some((charlie, 2000)));
+
+			// Bob stays escrowed since he might still win
+			assert_eq!(Balances::reserved_balance(&bob), 1000);
+
+			// The closing block is drawn at the first block after the bids, not when the auction is finalized
+			system::Module::<Test>::set_block_number(auction_closed + 1);
+			assert_err!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash), "The closing block of the candle auction is not drawn yet");
+			NamingServiceModule::on_initialize(auction_closed);
+			let closing = NamingServiceModule::candle_closing(domain_hash).unwrap();
+			assert!(ending_start <= closing && closing < auction_closed);
+			system::Module::<Test>::set_block_number(auction_closed + 7);
+			assert_ok!(NamingServiceModule::finalize_auction(Origin::signed(bob), domain_hash));
+
+			let domain = NamingServiceModule::domain(domain_hash);
+			let (winner, loser, price) = if closing < ending_start + 2 { (bob, charlie, 1000) } else { (charlie, bob, 2000) };
+			assert_eq!(domain.source, winner);
+			assert_eq!(domain.price, price);
+			assert_eq!(Balances::free_balance(&winner), initial - price - domain.deposit);
+			assert_eq!(Balances::free_balance(&loser), initial);
+			assert_eq!(Balances::reserved_balance(&loser), 0);
+			assert_eq!(NamingServiceModule::candle_bid(&domain_hash, &ending_start), None);
+			assert!(NamingServiceModule::candle_bidders(domain_hash).is_empty());
+			assert_eq!(NamingServiceModule::candle_closing(domain_hash), None);
+		});
+	}
+
+	#[test]
+	fn test_premium_decay() {
+		assert_eq!(<LinearDecay as PremiumDecay<u128, u64>>::premium(1000, 0, 100), 1000);
+		assert_eq!(<LinearDecay as PremiumDecay<u128, u64>>::premium(1000, 25, 100), 750);
+		assert_eq!(<LinearDecay as PremiumDecay<u128, u64>>::premium(1000, 100, 100), 0);
+
+		parameter_types! {
+			pub const HalfLife: u64 = 10;
+		}
+		assert_eq!(<ExponentialDecay<HalfLife> as PremiumDecay<u128, u64>>::premium(1000, 9, 100), 1000);
+		assert_eq!(<ExponentialDecay<HalfLife> as PremiumDecay<u128, u64>>::premium(1000, 25, 100), 250);
+		assert_eq!(<ExponentialDecay<HalfLife> as PremiumDecay<u128, u64>>::premium(1000, 100, 100), 0);
+	}
+
+	#[test]
+	fn test_buy_expired_domain() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			let initial = 1_000_000_000_000;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let deposit = NamingServiceModule::domain(domain_hash).deposit;
+			assert_err!(NamingServiceModule::buy_expired(Origin::signed(bob), domain_hash), "The domain is not expired or is in its grace period");
+
+			// Nobody can start an auction of an expired domain
+			let expiry = NamingServiceModule::expires_at(domain_hash) + GracePeriod::get();
+			system::Module::<Test>::set_block_number(expiry + PremiumPeriod::get() / 2);
+			assert_err!(NamingServiceModule::claim_auction(Origin::signed(bob), domain_hash, AuctionMode::English), "You are either not the source of the domain or the domain is expired");
+			assert_eq!(NamingServiceModule::premium(domain_hash), PremiumStartPrice::get() / 2);
+
+			let issuance = Balances::total_issuance();
+			assert_ok!(NamingServiceModule::buy_expired(Origin::signed(bob), domain_hash));
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(domain.source, bob);
+			assert_eq!(NamingServiceModule::expires_at(domain_hash), expiry + PremiumPeriod::get() / 2 + RegistrationPeriod::get());
+			assert_eq!(Balances::free_balance(&alice), initial);
+			assert_eq!(Balances::free_balance(&bob), initial - deposit - PremiumStartPrice::get() / 2);
+			assert_eq!(Balances::reserved_balance(&bob), deposit);
+			// The premium is burned
+			assert_eq!(Balances::total_issuance(), issuance - PremiumStartPrice::get() / 2);
+		});
+	}
+
+	#[test]
+	fn test_grace_period() {
+		with_externalities(&mut new_test_ext(), || {
+			let (alice, bob) = (1u64, 2u64);
+			assert_eq!(NamingServiceModule::domain_state(H256::zero()), DomainState::Available);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_eq!(NamingServiceModule::domain_state(domain_hash), DomainState::Active);
+
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			system::Module::<Test>::set_block_number(expiry);
+			assert_eq!(NamingServiceModule::domain_state(domain_hash), DomainState::Grace);
+			// The domain keeps resolving but nobody else can take it
+			assert_ok!(NamingServiceModule::resolve(Origin::signed(bob), domain_hash));
+			assert_err!(NamingServiceModule::buy_expired(Origin::signed(bob), domain_hash), "The domain is not expired or is in its grace period");
+			assert_err!(NamingServiceModule::renew(Origin::signed(bob), domain_hash), "You are either not the source of the domain or the domain is expired");
+			assert_eq!(NamingServiceModule::premium(domain_hash), 0);
+
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get());
+			assert_eq!(NamingServiceModule::domain_state(domain_hash), DomainState::Premium);
+			assert_eq!(NamingServiceModule::premium(domain_hash), PremiumStartPrice::get());
+			assert_err!(NamingServiceModule::renew(Origin::signed(alice), domain_hash), "You are either not the source of the domain or the domain is expired");
+
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get() + PremiumPeriod::get());
+			assert_eq!(NamingServiceModule::domain_state(domain_hash), DomainState::Available);
+		});
+	}
+
+	#[test]
+	fn test_renew_in_grace_period() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let expiry = NamingServiceModule::expires_at(domain_hash);
+			system::Module::<Test>::set_block_number(expiry + GracePeriod::get() - 1);
+			assert_ok!(NamingServiceModule::renew(Origin::signed(alice), domain_hash));
+			assert_eq!(NamingServiceModule::expires_at(domain_hash), expiry + RegistrationPeriod::get());
+			assert_eq!(NamingServiceModule::domain_state(domain_hash), DomainState::Active);
+		});
+	}
+
+	#[test]
+	fn test_configured_periods() {
+		with_externalities(&mut new_test_ext(), || {
+			let alice = 1u64;
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			let domain = NamingServiceModule::domain(domain_hash);
+			assert_eq!(domain.ttl, RegistrationPeriod::get());
+			assert_eq!(NamingServiceModule::expires_at(domain_hash), domain.registered_date + RegistrationPeriod::get());
+
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert_eq!(NamingServiceModule::domain(domain_hash).auction_closed, system::Module::<Test>::block_number() + AuctionPeriod::get());
+		});
+	}
 
 	#[test]
 	fn test_claim_auction() {
 		with_externalities(&mut new_test_ext(), || {
 			let alice = 1u64;
-			let dummy_hash = H256([2; 32]);
-			assert_ok!(NamingServiceModule::register_domain(Origin::signed(alice), dummy_hash));
-			assert_eq!(NamingServiceModule::domain(dummy_hash).source, alice);
+			let domain_hash = register(alice, b"hyungsukkang.dot");
+			assert_ok!(NamingServiceModule::claim_auction(Origin::signed(alice), domain_hash, AuctionMode::English));
+			assert!(NamingServiceModule::domain(domain_hash).available);
 		});
 	}
 }
@@ -3,10 +3,43 @@ use futures::{future, Future, sync::oneshot};
 use std::cell::RefCell;
 use tokio::runtime::Runtime;
 pub use substrate_cli::{VersionInfo, IntoExit, error};
-use substrate_cli::{informant, parse_and_prepare, ParseAndPrepare, NoCustom};
+use substrate_cli::{informant, parse_and_prepare, ParseAndPrepare, NoCustom, impl_augment_clap};
 use substrate_service::{AbstractService, Roles as ServiceRoles, Configuration};
 use crate::chain_spec;
-use log::info;
+use log::{info, warn};
+use std::net::SocketAddr;
+use structopt::StructOpt;
+
+/// Command line arguments of the node on top of the Substrate ones.
+#[derive(Debug, StructOpt, Clone)]
+pub struct CustomArgs {
+	/// Serve DNS for the names of the name service on this address e.g. 127.0.0.1:5353 (full nodes only)
+	#[structopt(long = "dns-listen", value_name = "ADDR")]
+	dns_listen: Option<SocketAddr>,
+	/// TLD answered from the chain, can be given multiple times
+	#[structopt(long = "dns-tld", value_name = "TLD", default_value = "dot")]
+	dns_tlds: Vec<String>,
+	/// Forward the DNS queries for other TLDs to this server instead of refusing them
+	#[structopt(long = "dns-upstream", value_name = "ADDR")]
+	dns_upstream: Option<SocketAddr>,
+	/// Answer DNS queries from the last finalized block instead of the best block
+	#[structopt(long = "dns-finalized")]
+	dns_finalized: bool,
+}
+
+impl_augment_clap!(CustomArgs);
+
+impl CustomArgs {
+	/// Configuration of the DNS server, if it is enabled
+	fn dns(&self) -> Option<crate::dns::DnsConfig> {
+		self.dns_listen.map(|listen| crate::dns::DnsConfig {
+			listen,
+			tlds: self.dns_tlds.iter().map(|tld| tld.trim_matches('.').to_ascii_lowercase()).collect(),
+			upstream: self.dns_upstream,
+			finalized: self.dns_finalized,
+		})
+	}
+}
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
@@ -15,9 +48,9 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 	E: IntoExit,
 {
 	type Config<T> = Configuration<(), T>;
-	match parse_and_prepare::<NoCustom, NoCustom, _>(&version, "substrate-node", args) {
+	match parse_and_prepare::<NoCustom, CustomArgs, _>(&version, "substrate-node", args) {
 		ParseAndPrepare::Run(cmd) => cmd.run(load_spec, exit,
-		|exit, _cli_args, _custom_args, config: Config<_>| {
+		|exit, _cli_args, custom_args, config: Config<_>| {
 			info!("{}", version.name);
 			info!("  version {}", config.full_version());
 			info!("  by {}, 2017, 2018", version.author);
@@ -25,6 +58,10 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 			info!("Node name: {}", config.name);
 			info!("Roles: {:?}", config.roles);
 			let runtime = Runtime::new().map_err(|e| format!("{:?}", e))?;
+			let dns = custom_args.dns();
+			if dns.is_some() && config.roles == ServiceRoles::LIGHT {
+				warn!("The DNS server only runs on full nodes, --dns-listen is ignored");
+			}
 			match config.roles {
 				ServiceRoles::LIGHT => run_until_exit(
 					runtime,
@@ -33,7 +70,7 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 				),
 				_ => run_until_exit(
 					runtime,
-					service::new_full(config).map_err(|e| format!("{:?}", e))?,
+					service::new_full(config, dns).map_err(|e| format!("{:?}", e))?,
 					exit
 				),
 			}.map_err(|e| format!("{:?}", e))
@@ -0,0 +1,809 @@
+//! DNS server of the node, answering queries for the names of the name service.
+//!
+//! Names under the configured TLDs are looked up in the chain state through the `NameServiceApi` of the
+//! runtime, at the best block or at the last finalized block. A and AAAA records come from the IP addresses
+//! of a domain, and the other types from its DNS record sets, with the TTLs set on chain. CNAMEs to names
+//! under the configured TLDs are followed. Names which are not registered or which no longer resolve (past
+//! their grace period) get NXDOMAIN. Negative answers carry the SOA of the TLD, whose serial is the block
+//! number the answer comes from, so that resolvers cache them. Names under other TLDs are forwarded to the upstream server if there
+//! is one, and refused otherwise.
+//!
+//! Punycode labels (`xn--`), which is how clients send internationalized names, are decoded to the
+//! Unicode name registered on chain. A label which is not valid punycode gets NXDOMAIN.
+//!
+//! The server runs as tasks of the service: UDP queries and TCP connections are served without blocking
+//! each other, and forwarded queries are answered once the upstream server responds.
+
+use std::{
+	cmp, io,
+	net::SocketAddr,
+	sync::{Arc, atomic::{AtomicUsize, Ordering}},
+	time::{Duration, Instant},
+};
+
+use futures::{future::{self, Either, Loop}, stream, sync::mpsc, prelude::*};
+use log::{debug, info, warn};
+use node_template_runtime::{
+	BlockNumber, DomainState, NameServiceApi, opaque::Block,
+	name_service::{name, dns::{self as records, RecordData, TYPE_A, TYPE_AAAA, TYPE_CNAME, TYPE_MX, TYPE_SRV, TYPE_TLSA, TYPE_TXT}},
+};
+use sr_primitives::{generic::BlockId, traits::{BlakeTwo256, ProvideRuntimeApi}};
+use substrate_client::blockchain::HeaderBackend;
+use tokio::{net::{TcpListener, TcpStream, UdpSocket}, timer::Timeout};
+
+/// Maximum size of a response over UDP without EDNS
+const MAX_UDP_SIZE: usize = 512;
+/// How long to wait for the upstream server, and for TCP clients to send a query or read its response
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a TCP connection is kept open at most, however many queries it sends
+const TCP_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum number of TCP connections served at once, the connections beyond it are closed right away
+const MAX_TCP_CONNECTIONS: usize = 64;
+
+const TYPE_SOA: u16 = 6;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+const CLASS_ANY: u16 = 255;
+
+const NOERROR: u8 = 0;
+const FORMERR: u8 = 1;
+const SERVFAIL: u8 = 2;
+const NXDOMAIN: u8 = 3;
+const NOTIMP: u8 = 4;
+const REFUSED: u8 = 5;
+
+/// Types answered to ANY queries
+const ANY_TYPES: [u16; 7] = [TYPE_A, TYPE_AAAA, TYPE_CNAME, TYPE_MX, TYPE_TXT, TYPE_SRV, TYPE_TLSA];
+
+/// Response to a query, `None` if the query is too broken to answer
+type Answer = Box<dyn Future<Item = Option<Vec<u8>>, Error = ()> + Send>;
+
+/// Configuration of the DNS server.
+#[derive(Clone, Debug)]
+pub struct DnsConfig {
+	/// Address to listen on for UDP and TCP queries
+	pub listen: SocketAddr,
+	/// TLDs answered from the chain e.g. `dot`
+	pub tlds: Vec<String>,
+	/// Server to forward the queries for other TLDs to, they are refused without one
+	pub upstream: Option<SocketAddr>,
+	/// Whether to answer from the last finalized block instead of the best block
+	pub finalized: bool,
+}
+
+/// Bind the listen address of the config, returning the task serving DNS on it. The task never ends,
+/// the queries and the TCP connections it takes are served in tasks of their own.
+pub fn start<C>(client: Arc<C>, config: DnsConfig) -> io::Result<impl Future<Item = (), Error = ()> + Send> where
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: NameServiceApi<Block>,
+{
+	let udp = UdpSocket::bind(&config.listen)?;
+	let mut tcp = TcpListener::bind(&config.listen)?;
+	info!("DNS server listening on {} for {:?}", config.listen, config.tlds);
+	let server = Arc::new(Server { client, config });
+
+	let (responses, pending) = mpsc::unbounded();
+	let udp = UdpServer { server: server.clone(), socket: udp, buf: vec![0u8; 4096], responses, pending, sending: None };
+
+	let connections = Arc::new(AtomicUsize::new(0));
+	let tcp = stream::poll_fn(move || -> Poll<Option<TcpStream>, ()> {
+		loop {
+			match tcp.poll_accept() {
+				Ok(Async::Ready((stream, _))) => return Ok(Async::Ready(Some(stream))),
+				Ok(Async::NotReady) => return Ok(Async::NotReady),
+				Err(e) => warn!("DNS server failed to accept a TCP connection: {}", e),
+			}
+		}
+	}).for_each(move |stream| {
+		let slot = match ConnectionSlot::take(&connections) {
+			Some(slot) => slot,
+			None => {
+				debug!("DNS server is serving {} TCP connections, closing a new one", MAX_TCP_CONNECTIONS);
+				return Ok(());
+			}
+		};
+		tokio::spawn(server.clone().serve_tcp(stream).then(move |result| {
+			drop(slot);
+			if let Err(e) = result {
+				debug!("DNS server closed a TCP connection: {}", e);
+			}
+			Ok(())
+		}));
+		Ok(())
+	});
+
+	Ok(udp.select(tcp).then(|_| Ok::<_, ()>(())))
+}
+
+/// One of the `MAX_TCP_CONNECTIONS` TCP connections, given back when it is dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+	/// Take a slot for a new connection, `None` if every slot is taken
+	fn take(connections: &Arc<AtomicUsize>) -> Option<Self> {
+		if connections.fetch_add(1, Ordering::SeqCst) >= MAX_TCP_CONNECTIONS {
+			connections.fetch_sub(1, Ordering::SeqCst);
+			return None;
+		}
+		Some(ConnectionSlot(connections.clone()))
+	}
+}
+
+impl Drop for ConnectionSlot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Task receiving the queries over UDP and sending their responses. Every query is answered in a task
+/// of its own, which hands the response back through the channel.
+struct UdpServer<C> {
+	server: Arc<Server<C>>,
+	socket: UdpSocket,
+	buf: Vec<u8>,
+	responses: mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>,
+	pending: mpsc::UnboundedReceiver<(Vec<u8>, SocketAddr)>,
+	/// response waiting for the socket to be writable
+	sending: Option<(Vec<u8>, SocketAddr)>,
+}
+
+impl<C> Future for UdpServer<C> where
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: NameServiceApi<Block>,
+{
+	type Item = ();
+	type Error = ();
+
+	fn poll(&mut self) -> Poll<(), ()> {
+		loop {
+			if self.sending.is_none() {
+				match self.pending.poll() {
+					Ok(Async::Ready(Some(response))) => self.sending = Some(response),
+					_ => break,
+				}
+			}
+			if let Some((ref response, peer)) = self.sending {
+				match self.socket.poll_send_to(response, &peer) {
+					Ok(Async::Ready(_)) => {},
+					Ok(Async::NotReady) => break,
+					Err(e) => debug!("DNS server failed to answer {}: {}", peer, e),
+				}
+			}
+			self.sending = None;
+		}
+
+		loop {
+			let (len, peer) = match self.socket.poll_recv_from(&mut self.buf) {
+				Ok(Async::Ready(received)) => received,
+				Ok(Async::NotReady) => return Ok(Async::NotReady),
+				Err(e) => {
+					warn!("DNS server failed to receive over UDP: {}", e);
+					continue;
+				}
+			};
+			let responses = self.responses.clone();
+			tokio::spawn(self.server.handle(&self.buf[..len]).map(move |response| {
+				if let Some(response) = response {
+					// Fails only once the server is gone, along with the peer's query
+					let _ = responses.unbounded_send((truncate(response), peer));
+				}
+			}));
+		}
+	}
+}
+
+struct Server<C> {
+	client: Arc<C>,
+	config: DnsConfig,
+}
+
+impl<C> Server<C> where
+	C: ProvideRuntimeApi + HeaderBackend<Block>,
+	C::Api: NameServiceApi<Block>,
+{
+	/// Answer the length prefixed queries of a TCP connection until it is closed. Each query has to be
+	/// received and answered within `TIMEOUT`, and the connection is closed after `TCP_CONNECTION_TIMEOUT`.
+	fn serve_tcp(self: Arc<Self>, stream: TcpStream) -> impl Future<Item = (), Error = io::Error> {
+		let deadline = Instant::now() + TCP_CONNECTION_TIMEOUT;
+		future::loop_fn(stream, move |stream| {
+			let server = self.clone();
+			let exchange = tokio::io::read_exact(stream, [0u8; 2])
+				.and_then(|(stream, len)| tokio::io::read_exact(stream, vec![0u8; u16::from_be_bytes(len) as usize]))
+				.and_then(move |(stream, query)| server.handle(&query).then(move |response| match response {
+					Ok(Some(response)) => {
+						let mut message = (response.len() as u16).to_be_bytes().to_vec();
+						message.extend_from_slice(&response);
+						Either::A(tokio::io::write_all(stream, message).map(|(stream, _)| stream))
+					},
+					_ => Either::B(future::ok(stream)),
+				}));
+			before(exchange, cmp::min(Instant::now() + TIMEOUT, deadline)).map(Loop::<(), TcpStream>::Continue)
+		})
+	}
+
+	/// Response to the query
+	fn handle(&self, query: &[u8]) -> Answer {
+		let query = match Query::parse(query) {
+			Ok(query) => query,
+			Err(Some(header)) => return Box::new(future::ok(Some(header.response(FORMERR)))),
+			Err(None) => return Box::new(future::ok(None)),
+		};
+		if query.header.opcode() != 0 {
+			return Box::new(future::ok(Some(query.header.response(NOTIMP))));
+		}
+
+		let name = query.name.to_ascii_lowercase();
+		if !self.in_zone(&name) {
+			return self.forward(&query);
+		}
+
+		let (at, number) = self.at();
+		let tld = name.rsplit('.').next().unwrap_or_default();
+		let answers = match to_unicode(&name) {
+			Some(name) => self.lookup(&at, &name, query.qtype),
+			None => Ok(None),
+		};
+		Box::new(future::ok(Some(match answers {
+			Ok(Some(ref answers)) if answers.is_empty() => query.response(NOERROR, &[], &[soa(tld, number)]),
+			Ok(Some(answers)) => query.response(NOERROR, &answers, &[]),
+			Ok(None) => query.response(NXDOMAIN, &[], &[soa(tld, number)]),
+			Err(e) => {
+				warn!("DNS server failed to read the chain state for {}: {}", name, e);
+				query.response(SERVFAIL, &[], &[])
+			}
+		})))
+	}
+
+	/// Block the answers come from with its number, the best block or the last finalized block
+	fn at(&self) -> (BlockId<Block>, BlockNumber) {
+		let info = self.client.info();
+		if self.config.finalized {
+			(BlockId::hash(info.finalized_hash), info.finalized_number)
+		} else {
+			(BlockId::hash(info.best_hash), info.best_number)
+		}
+	}
+
+	/// Whether the name is under one of the TLDs answered from the chain
+	fn in_zone(&self, name: &str) -> bool {
+		let tld = name.rsplit('.').next().unwrap_or_default();
+		self.config.tlds.iter().any(|t| t.as_str() == tld)
+	}
+
+	/// Records of the type for the name, `None` if the name does not resolve
+	fn lookup(&self, at: &BlockId<Block>, name: &str, qtype: u16) -> Result<Option<Vec<Record>>, String> {
+		if name::validate_owner(name.as_bytes()).is_err() {
+			return Ok(None);
+		}
+		let api = self.client.runtime_api();
+		let dns_records = |domain_hash, rtype| api.dns_records(at, domain_hash, rtype).map_err(|e| format!("{:?}", e));
+
+		let mut answers = Vec::new();
+		let mut owner = name.to_string();
+		// The runtime refuses CNAME loops, this only bounds the chains of aliases
+		for hop in 0..records::MAX_CNAME_CHAIN {
+			let domain_hash = name::namehash::<BlakeTwo256>(owner.as_bytes());
+			let state = api.state(at, domain_hash).map_err(|e| format!("{:?}", e))?;
+			if state != DomainState::Active && state != DomainState::Grace {
+				// An alias to a name which does not resolve is answered with the CNAME alone
+				return Ok(if hop == 0 { None } else { Some(answers) });
+			}
+			let record_owner = if hop == 0 { None } else { Some(owner.clone()) };
+
+			if qtype != TYPE_CNAME && qtype != TYPE_ANY {
+				if let Some((ttl, mut cname)) = dns_records(domain_hash, TYPE_CNAME)? {
+					let data = cname.remove(0);
+					let target = match RecordData::from_wire(TYPE_CNAME, &data) {
+						Ok(RecordData::Cname(target)) => String::from_utf8(target).map_err(|e| e.to_string())?,
+						_ => return Err(format!("{} has a broken CNAME", owner)),
+					};
+					answers.push(Record { owner: record_owner, rtype: TYPE_CNAME, ttl, data });
+					if !self.in_zone(&target) {
+						break;
+					}
+					owner = target;
+					continue;
+				}
+			}
+
+			let rtypes = if qtype == TYPE_ANY { &ANY_TYPES[..] } else { std::slice::from_ref(&qtype) };
+			for rtype in rtypes {
+				if let Some((ttl, data)) = dns_records(domain_hash, *rtype)? {
+					answers.extend(data.into_iter().map(|data| Record { owner: record_owner.clone(), rtype: *rtype, ttl, data }));
+				}
+			}
+			break;
+		}
+		Ok(Some(answers))
+	}
+
+	/// Response of the upstream server to the query, or REFUSED without an upstream server
+	fn forward(&self, query: &Query) -> Answer {
+		let upstream = match self.config.upstream {
+			Some(upstream) => upstream,
+			None => return Box::new(future::ok(Some(query.response(REFUSED, &[], &[])))),
+		};
+		let local: SocketAddr = if upstream.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+		let (raw, id) = (query.raw.clone(), query.raw[..2].to_vec());
+		let relay = future::result(UdpSocket::bind(&local))
+			.and_then(move |socket| socket.send_dgram(raw, &upstream))
+			.and_then(move |(socket, _)| future::loop_fn(socket, move |socket| {
+				let id = id.clone();
+				socket.recv_dgram(vec![0u8; 4096]).map(move |(socket, buf, len, peer)| {
+					// Ignore anything but the answer to this query
+					if peer == upstream && len >= 2 && buf[..2] == id[..] {
+						Loop::Break(buf[..len].to_vec())
+					} else {
+						Loop::Continue(socket)
+					}
+				})
+			}));
+		let name = query.name.clone();
+		let servfail = query.response(SERVFAIL, &[], &[]);
+		Box::new(before(relay, Instant::now() + TIMEOUT).then(move |response| Ok(Some(response.unwrap_or_else(|e| {
+			debug!("DNS server failed to forward {} to {}: {}", name, upstream, e);
+			servfail
+		})))))
+	}
+}
+
+/// Fail with `TimedOut` unless the future completes before the deadline
+fn before<F: Future<Error = io::Error>>(future: F, deadline: Instant) -> impl Future<Item = F::Item, Error = io::Error> {
+	Timeout::new_at(future, deadline).map_err(|e| e.into_inner().unwrap_or_else(|| io::ErrorKind::TimedOut.into()))
+}
+
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Name with its punycode labels (`xn--`) decoded, `None` if one of them is not the punycode of a
+/// label with non-ASCII characters
+fn to_unicode(name: &str) -> Option<String> {
+	let labels = name.split('.').map(|label| if label.starts_with("xn--") {
+		punycode_decode(&label[4..]).filter(|label| !label.is_ascii())
+	} else {
+		Some(label.to_string())
+	}).collect::<Option<Vec<_>>>()?;
+	Some(labels.join("."))
+}
+
+/// Decode a lowercase punycode string as specified in RFC 3492
+fn punycode_decode(input: &str) -> Option<String> {
+	let (basic, extended) = match input.rfind('-') {
+		Some(end) => (&input[..end], &input[end + 1..]),
+		None => ("", input),
+	};
+	if !basic.is_ascii() {
+		return None;
+	}
+	let mut output: Vec<char> = basic.chars().collect();
+	let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+	let mut digits = extended.bytes().peekable();
+	while digits.peek().is_some() {
+		let old_i = i;
+		let mut w = 1u32;
+		let mut k = BASE;
+		loop {
+			let digit = match digits.next()? {
+				c @ b'a'..=b'z' => c - b'a',
+				c @ b'0'..=b'9' => c - b'0' + 26,
+				_ => return None,
+			} as u32;
+			i = i.checked_add(digit.checked_mul(w)?)?;
+			let t = if k <= bias { TMIN } else if k >= bias + TMAX { TMAX } else { k - bias };
+			if digit < t {
+				break;
+			}
+			w = w.checked_mul(BASE - t)?;
+			k += BASE;
+		}
+		let len = output.len() as u32 + 1;
+		bias = adapt(i - old_i, len, old_i == 0);
+		n = n.checked_add(i / len)?;
+		i %= len;
+		output.insert(i as usize, std::char::from_u32(n).filter(|c| !c.is_ascii())?);
+		i += 1;
+	}
+	Some(output.into_iter().collect())
+}
+
+/// Bias adaptation of punycode
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+	let mut delta = if first { delta / DAMP } else { delta / 2 };
+	delta += delta / points;
+	let mut k = 0;
+	while delta > (BASE - TMIN) * TMAX / 2 {
+		delta /= BASE - TMIN;
+		k += BASE;
+	}
+	k + (BASE - TMIN + 1) * delta / (delta + SKEW)
+}
+
+/// Drop the answers of a response which does not fit in a UDP datagram and set its TC flag,
+/// so that the client retries over TCP
+fn truncate(response: Vec<u8>) -> Vec<u8> {
+	if response.len() <= MAX_UDP_SIZE {
+		return response;
+	}
+	let question_end = match question_end(&response) {
+		Some(end) => end,
+		None => return response[..MAX_UDP_SIZE].to_vec(),
+	};
+	let mut truncated = response[..question_end].to_vec();
+	truncated[2] |= 0x02;
+	// no answer, authority or additional records
+	for count in truncated[6..12].iter_mut() {
+		*count = 0;
+	}
+	truncated
+}
+
+/// Offset of the end of the first question of a message
+fn question_end(message: &[u8]) -> Option<usize> {
+	let mut pos = 12;
+	loop {
+		let len = *message.get(pos)? as usize;
+		pos += 1;
+		if len == 0 {
+			return Some(pos + 4).filter(|end| *end <= message.len());
+		}
+		if len & 0xc0 != 0 {
+			return None;
+		}
+		pos += len;
+	}
+}
+
+/// SOA of the TLD for the authority section of negative answers. Its serial is the number of the block
+/// the answer comes from and its minimum, the TTL of negative answers, is the default TTL of the records
+fn soa(tld: &str, serial: BlockNumber) -> Record {
+	let mut data = Vec::new();
+	records::encode_name(tld.as_bytes(), &mut data);
+	records::encode_name(format!("hostmaster.{}", tld).as_bytes(), &mut data);
+	// serial, refresh, retry, expire and minimum
+	for value in &[serial, 3600, 600, 86400, records::DEFAULT_TTL] {
+		data.extend_from_slice(&value.to_be_bytes());
+	}
+	Record { owner: Some(tld.to_string()), rtype: TYPE_SOA, ttl: records::DEFAULT_TTL, data }
+}
+
+/// Resource record of an answer, of class IN
+struct Record {
+	/// name of the record, `None` for the queried name
+	owner: Option<String>,
+	rtype: u16,
+	ttl: u32,
+	data: Vec<u8>,
+}
+
+/// Header of a DNS message
+#[derive(Clone, Copy)]
+struct Header {
+	id: u16,
+	flags: u16,
+}
+
+impl Header {
+	fn opcode(&self) -> u16 {
+		(self.flags >> 11) & 0x0f
+	}
+
+	/// Response flags to a query with these flags: QR and AA set, opcode and RD copied
+	fn response_flags(&self, rcode: u8) -> u16 {
+		0x8000 | 0x0400 | (self.flags & 0x7900) | u16::from(rcode)
+	}
+
+	/// Response with no question to a query which cannot be parsed
+	fn response(&self, rcode: u8) -> Vec<u8> {
+		let mut message = Vec::with_capacity(12);
+		message.extend_from_slice(&self.id.to_be_bytes());
+		message.extend_from_slice(&self.response_flags(rcode).to_be_bytes());
+		message.extend_from_slice(&[0u8; 8]);
+		message
+	}
+}
+
+/// Query with a single question
+struct Query {
+	header: Header,
+	/// queried name without the trailing dot
+	name: String,
+	qtype: u16,
+	qclass: u16,
+	/// the query as received, for forwarding
+	raw: Vec<u8>,
+	/// the question as received, for the response
+	question: Vec<u8>,
+}
+
+impl Query {
+	/// Parse the query, failing with its header if only the header could be parsed
+	fn parse(message: &[u8]) -> Result<Self, Option<Header>> {
+		if message.len() < 12 {
+			return Err(None);
+		}
+		let header = Header {
+			id: u16::from_be_bytes([message[0], message[1]]),
+			flags: u16::from_be_bytes([message[2], message[3]]),
+		};
+		// a query (QR unset) with a single question
+		if header.flags & 0x8000 != 0 || message[4..6] != [0, 1] {
+			return Err(Some(header));
+		}
+		let end = question_end(message).ok_or(Some(header))?;
+
+		let mut labels = Vec::new();
+		let mut pos = 12;
+		while message[pos] != 0 {
+			let len = message[pos] as usize;
+			let label = std::str::from_utf8(&message[pos + 1..pos + 1 + len]).map_err(|_| Some(header))?;
+			labels.push(label);
+			pos += 1 + len;
+		}
+		Ok(Query {
+			header,
+			name: labels.join("."),
+			qtype: u16::from_be_bytes([message[end - 4], message[end - 3]]),
+			qclass: u16::from_be_bytes([message[end - 2], message[end - 1]]),
+			raw: message.to_vec(),
+			question: message[12..end].to_vec(),
+		})
+	}
+
+	/// Authoritative response to the query with the answers and the authority records
+	fn response(&self, rcode: u8, answers: &[Record], authority: &[Record]) -> Vec<u8> {
+		// Chain names only exist in class IN
+		let (answers, authority) = if self.qclass == CLASS_IN || self.qclass == CLASS_ANY { (answers, authority) } else { (&[][..], &[][..]) };
+		let mut message = Vec::with_capacity(512);
+		message.extend_from_slice(&self.header.id.to_be_bytes());
+		message.extend_from_slice(&self.header.response_flags(rcode).to_be_bytes());
+		message.extend_from_slice(&1u16.to_be_bytes());
+		message.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+		message.extend_from_slice(&(authority.len() as u16).to_be_bytes());
+		message.extend_from_slice(&[0u8; 2]);
+		message.extend_from_slice(&self.question);
+		for answer in answers.iter().chain(authority) {
+			match answer.owner {
+				Some(ref owner) => records::encode_name(owner.as_bytes(), &mut message),
+				// pointer to the name of the question right after the header
+				None => message.extend_from_slice(&[0xc0, 12]),
+			}
+			message.extend_from_slice(&answer.rtype.to_be_bytes());
+			message.extend_from_slice(&CLASS_IN.to_be_bytes());
+			message.extend_from_slice(&answer.ttl.to_be_bytes());
+			message.extend_from_slice(&(answer.data.len() as u16).to_be_bytes());
+			message.extend_from_slice(&answer.data);
+		}
+		message
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn query(name: &str, qtype: u16) -> Vec<u8> {
+		// id 0x1234, RD set, one question
+		let mut message = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+		records::encode_name(name.as_bytes(), &mut message);
+		message.extend_from_slice(&qtype.to_be_bytes());
+		message.extend_from_slice(&CLASS_IN.to_be_bytes());
+		message
+	}
+
+	/// Id of the header the query is rejected with, `None` if it is dropped
+	fn rejected(message: &[u8]) -> Option<u16> {
+		match Query::parse(message) {
+			Ok(_) => panic!("the query is parsed"),
+			Err(header) => header.map(|header| header.id),
+		}
+	}
+
+	fn read_u16(message: &[u8], pos: usize) -> u16 {
+		u16::from_be_bytes([message[pos], message[pos + 1]])
+	}
+
+	/// Name at the position of the message following compression pointers, and the position after it
+	fn read_name(message: &[u8], mut pos: usize) -> (String, usize) {
+		let mut labels = Vec::new();
+		let mut end = None;
+		loop {
+			let len = message[pos] as usize;
+			if len & 0xc0 == 0xc0 {
+				end = end.or(Some(pos + 2));
+				pos = (len & 0x3f) << 8 | message[pos + 1] as usize;
+				continue;
+			}
+			pos += 1;
+			if len == 0 {
+				break;
+			}
+			labels.push(String::from_utf8(message[pos..pos + len].to_vec()).unwrap());
+			pos += len;
+		}
+		(labels.join("."), end.unwrap_or(pos))
+	}
+
+	/// (owner, type, ttl, RDATA) of the records of a response, the answers then the authority records
+	fn records_of(message: &[u8]) -> Vec<(String, u16, u32, Vec<u8>)> {
+		let count = read_u16(message, 6) + read_u16(message, 8) + read_u16(message, 10);
+		let mut pos = question_end(message).unwrap();
+		(0..count).map(|_| {
+			let (owner, end) = read_name(message, pos);
+			assert_eq!(read_u16(message, end + 2), CLASS_IN);
+			let ttl = u32::from_be_bytes([message[end + 4], message[end + 5], message[end + 6], message[end + 7]]);
+			let len = read_u16(message, end + 8) as usize;
+			pos = end + 10 + len;
+			(owner, read_u16(message, end), ttl, message[end + 10..pos].to_vec())
+		}).collect()
+	}
+
+	fn record(owner: Option<&str>, rtype: u16, ttl: u32, data: Vec<u8>) -> Record {
+		Record { owner: owner.map(str::to_string), rtype, ttl, data }
+	}
+
+	#[test]
+	fn decodes_punycode_labels() {
+		assert_eq!(to_unicode("xn--caf-dma.dot"), Some("café.dot".to_string()));
+		assert_eq!(to_unicode("www.xn--bcher-kva.dot"), Some("www.bücher.dot".to_string()));
+		assert_eq!(to_unicode("xn--9n2bp8q.dot"), Some("실례.dot".to_string()));
+		assert_eq!(to_unicode("xn--ihqwcrb4cv8a8dqg056pqjye.dot"), Some("他们为什么不说中文.dot".to_string()));
+		assert_eq!(to_unicode("hyungsukkang.dot"), Some("hyungsukkang.dot".to_string()));
+		// the punycode of an ASCII label, a digit which is not punycode and a cut off label
+		assert_eq!(to_unicode("xn--hello-.dot"), None);
+		assert_eq!(to_unicode("xn--caf-dm!.dot"), None);
+		assert_eq!(to_unicode("xn--caf-dm.dot"), None);
+		assert_eq!(to_unicode("xn--.dot"), None);
+	}
+
+	#[test]
+	fn parses_queries() {
+		let message = query("HyungsukKang.dot", TYPE_AAAA);
+		let query = Query::parse(&message).ok().unwrap();
+		assert_eq!(query.header.id, 0x1234);
+		assert_eq!(query.header.opcode(), 0);
+		assert_eq!(query.name, "HyungsukKang.dot");
+		assert_eq!(query.qtype, TYPE_AAAA);
+		assert_eq!(query.qclass, CLASS_IN);
+		assert_eq!(query.raw, message);
+		assert_eq!(query.question, message[12..].to_vec());
+	}
+
+	#[test]
+	fn rejects_malformed_queries() {
+		let message = query("hyungsukkang.dot", TYPE_A);
+		// shorter than a header
+		assert_eq!(rejected(&message[..11]), None);
+		// a response
+		let mut response = message.clone();
+		response[2] |= 0x80;
+		assert_eq!(rejected(&response), Some(0x1234));
+		// no question or two questions
+		let mut questions = message.clone();
+		questions[5] = 0;
+		assert_eq!(rejected(&questions), Some(0x1234));
+		questions[5] = 2;
+		assert_eq!(rejected(&questions), Some(0x1234));
+		// the name or the type and class are cut off
+		assert_eq!(rejected(&message[..16]), Some(0x1234));
+		assert_eq!(rejected(&message[..message.len() - 1]), Some(0x1234));
+		// a label which is not UTF-8
+		let mut binary = message.clone();
+		binary[13] = 0xff;
+		assert_eq!(rejected(&binary), Some(0x1234));
+	}
+
+	#[test]
+	fn rejects_compression_pointers_in_questions() {
+		let mut message = query("hyungsukkang.dot", TYPE_A);
+		// hyungsukkang followed by a pointer to itself instead of the dot label
+		message.truncate(12 + 13);
+		message.extend_from_slice(&[0xc0, 12]);
+		message.extend_from_slice(&TYPE_A.to_be_bytes());
+		message.extend_from_slice(&CLASS_IN.to_be_bytes());
+		assert_eq!(question_end(&message), None);
+		assert_eq!(rejected(&message), Some(0x1234));
+
+		let mut pointer = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 0xc0, 12];
+		pointer.extend_from_slice(&[0, 1, 0, 1]);
+		assert_eq!(question_end(&pointer), None);
+	}
+
+	#[test]
+	fn finds_the_end_of_the_question() {
+		let message = query("hyungsukkang.dot", TYPE_A);
+		assert_eq!(question_end(&message), Some(message.len()));
+		assert_eq!(question_end(&message[..message.len() - 1]), None);
+		// a label running past the end of the message
+		assert_eq!(question_end(&message[..20]), None);
+		assert_eq!(question_end(&message[..12]), None);
+	}
+
+	#[test]
+	fn truncates_large_responses() {
+		let message = query("hyungsukkang.dot", TYPE_TXT);
+		let query = Query::parse(&message).ok().unwrap();
+		let small = query.response(NOERROR, &[record(None, TYPE_A, 60, vec![127, 0, 0, 1])], &[]);
+		assert_eq!(truncate(small.clone()), small);
+
+		let txt = RecordData::Txt(vec![vec![b'a'; 255]]).to_wire();
+		let answers: Vec<_> = (0..4).map(|_| record(None, TYPE_TXT, 60, txt.clone())).collect();
+		let large = query.response(NOERROR, &answers, &[]);
+		assert!(large.len() > MAX_UDP_SIZE);
+		let truncated = truncate(large.clone());
+		assert_eq!(truncated.len(), message.len());
+		assert_eq!(truncated[..2], large[..2]);
+		assert_eq!(truncated[2] & 0x02, 0x02);
+		assert_eq!(truncated[4..6], [0, 1]);
+		assert_eq!(truncated[6..12], [0u8; 6]);
+		assert_eq!(truncated[12..], message[12..]);
+	}
+
+	#[test]
+	fn encodes_record_sets_with_their_ttls() {
+		let message = query("hyungsukkang.dot", TYPE_ANY);
+		let query = Query::parse(&message).ok().unwrap();
+		let mut ipv6 = vec![0u8; 16];
+		ipv6[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+		ipv6[15] = 1;
+		let txt = RecordData::Txt(vec![b"email=a@b.c".to_vec(), b"v=spf1 -all".to_vec()]);
+		let answers = vec![
+			record(None, TYPE_A, 60, vec![127, 0, 0, 1]),
+			record(None, TYPE_A, 60, vec![10, 0, 0, 1]),
+			record(None, TYPE_AAAA, 120, ipv6.clone()),
+			record(Some("www.hyungsukkang.dot"), TYPE_TXT, 3600, txt.to_wire()),
+		];
+		let response = query.response(NOERROR, &answers, &[]);
+		assert_eq!(read_u16(&response, 6), 4);
+		assert_eq!(read_u16(&response, 8), 0);
+
+		let records = records_of(&response);
+		assert_eq!(records, vec![
+			("hyungsukkang.dot".to_string(), TYPE_A, 60, vec![127, 0, 0, 1]),
+			("hyungsukkang.dot".to_string(), TYPE_A, 60, vec![10, 0, 0, 1]),
+			("hyungsukkang.dot".to_string(), TYPE_AAAA, 120, ipv6),
+			("www.hyungsukkang.dot".to_string(), TYPE_TXT, 3600, txt.to_wire()),
+		]);
+		assert_eq!(RecordData::from_wire(TYPE_TXT, &records[3].3), Ok(txt));
+	}
+
+	#[test]
+	fn answers_service_names() {
+		let message = query("_sip._tcp.HyungsukKang.dot", TYPE_SRV);
+		let query = Query::parse(&message).ok().unwrap();
+		let name = to_unicode(&query.name.to_ascii_lowercase()).unwrap();
+		assert_eq!(name, "_sip._tcp.hyungsukkang.dot");
+		assert_eq!(name::validate_owner(name.as_bytes()), Ok(()));
+		assert_eq!(name::namehash::<BlakeTwo256>(name.as_bytes()), name::subnode::<BlakeTwo256>(&name::namehash::<BlakeTwo256>(b"_tcp.hyungsukkang.dot"), b"_sip"));
+
+		let srv = RecordData::Srv { priority: 10, weight: 5, port: 5060, target: b"sip.hyungsukkang.dot".to_vec() };
+		let response = query.response(NOERROR, &[record(None, TYPE_SRV, 600, srv.to_wire())], &[]);
+		let records = records_of(&response);
+		assert_eq!(records, vec![("_sip._tcp.HyungsukKang.dot".to_string(), TYPE_SRV, 600, srv.to_wire())]);
+		assert_eq!(RecordData::from_wire(TYPE_SRV, &records[0].3), Ok(srv));
+	}
+
+	#[test]
+	fn negative_responses_carry_the_soa() {
+		let message = query("nobody.dot", TYPE_A);
+		let query = Query::parse(&message).ok().unwrap();
+		let response = query.response(NXDOMAIN, &[], &[soa("dot", 42)]);
+		assert_eq!(read_u16(&response, 2) & 0x000f, u16::from(NXDOMAIN));
+		assert_eq!(read_u16(&response, 6), 0);
+		assert_eq!(read_u16(&response, 8), 1);
+
+		let records = records_of(&response);
+		assert_eq!(records.len(), 1);
+		let (ref owner, rtype, ttl, ref data) = records[0];
+		assert_eq!((owner.as_str(), rtype, ttl), ("dot", TYPE_SOA, records::DEFAULT_TTL));
+		let (mname, pos) = read_name(data, 0);
+		let (rname, pos) = read_name(data, pos);
+		assert_eq!((mname.as_str(), rname.as_str()), ("dot", "hostmaster.dot"));
+		let values: Vec<u32> = data[pos..].chunks(4).map(|value| u32::from_be_bytes([value[0], value[1], value[2], value[3]])).collect();
+		assert_eq!(values, vec![42, 3600, 600, 86400, records::DEFAULT_TTL]);
+	}
+}
@@ -7,6 +7,8 @@ mod chain_spec;
 #[macro_use]
 mod service;
 mod cli;
+mod rpc;
+mod dns;
 
 pub use substrate_cli::{VersionInfo, IntoExit, error};
 
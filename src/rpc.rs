@@ -0,0 +1,213 @@
+//! Name service RPC of the node, exposed under the `names_` prefix.
+//!
+//! The queries go through the `NameServiceApi` of the runtime, so they work on the full and the light
+//! client alike. Every query takes an optional block hash to look at, the best block by default.
+
+use std::{net::{Ipv4Addr, Ipv6Addr}, sync::Arc};
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{
+	AccountId, Balance, BlockNumber, Hash, NameServiceApi, opaque::Block,
+	name_service::{name, AuctionMode, DomainState},
+};
+use primitives::Bytes;
+use serde::{Deserialize, Serialize};
+use sr_primitives::{generic::BlockId, traits::{BlakeTwo256, ProvideRuntimeApi}};
+use substrate_client::blockchain::HeaderBackend;
+
+/// Maximum number of domains `names_listByOwner` returns at once
+const MAX_PAGE_SIZE: u64 = node_template_runtime::name_service::MAX_PAGE_SIZE;
+
+/// A domain with its records decoded for clients.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Domain {
+	pub hash: Hash,
+	pub name: String,
+	pub source: AccountId,
+	pub controller: AccountId,
+	pub price: Balance,
+	pub deposit: Balance,
+	pub expires_at: BlockNumber,
+	pub state: DomainState,
+	pub in_auction: bool,
+	pub ipv4: Vec<Ipv4Addr>,
+	pub ipv6: Vec<Ipv6Addr>,
+	pub addresses: Vec<(u32, Bytes)>,
+	pub content_hash: Option<Bytes>,
+}
+
+impl From<node_template_runtime::DomainInfo> for Domain {
+	fn from(info: node_template_runtime::DomainInfo) -> Self {
+		Domain {
+			hash: info.hash,
+			// The runtime only stores validated UTF-8 names
+			name: String::from_utf8_lossy(&info.name).into_owned(),
+			source: info.source,
+			controller: info.controller,
+			price: info.price,
+			deposit: info.deposit,
+			expires_at: info.expires_at,
+			state: info.state,
+			in_auction: info.in_auction,
+			ipv4: info.ipv4.into_iter().map(Ipv4Addr::from).collect(),
+			ipv6: info.ipv6.into_iter().map(Ipv6Addr::from).collect(),
+			addresses: info.addresses.into_iter().map(|(coin_type, address)| (coin_type, Bytes(address))).collect(),
+			content_hash: info.content_hash.map(Bytes),
+		}
+	}
+}
+
+/// The auction of a domain.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionStatus {
+	pub mode: AuctionMode,
+	pub highest_bid: Balance,
+	pub bidder: AccountId,
+	pub second_bid: Balance,
+	pub closes_at: BlockNumber,
+	pub ends_at: BlockNumber,
+	pub ending_start: Option<BlockNumber>,
+}
+
+impl From<node_template_runtime::AuctionStatus> for AuctionStatus {
+	fn from(status: node_template_runtime::AuctionStatus) -> Self {
+		AuctionStatus {
+			mode: status.mode,
+			highest_bid: status.highest_bid,
+			bidder: status.bidder,
+			second_bid: status.second_bid,
+			closes_at: status.closes_at,
+			ends_at: status.ends_at,
+			ending_start: status.ending_start,
+		}
+	}
+}
+
+/// Name service RPC methods.
+#[rpc]
+pub trait NamesApi<BlockHash> {
+	/// Domain with the normalized name e.g. `hyungsukkang.dot`, `null` if it is not registered.
+	#[rpc(name = "names_resolve")]
+	fn resolve(&self, name: String, at: Option<BlockHash>) -> Result<Option<Domain>>;
+
+	/// Primary name of the account, if it still resolves to the account.
+	#[rpc(name = "names_reverse")]
+	fn reverse(&self, account: AccountId, at: Option<BlockHash>) -> Result<Option<Domain>>;
+
+	/// Namehashes of the domains owned by the account, a page of at most 100 of them from `start` on.
+	#[rpc(name = "names_listByOwner")]
+	fn list_by_owner(&self, account: AccountId, start: Option<u64>, limit: Option<u64>, at: Option<BlockHash>) -> Result<Vec<Hash>>;
+
+	/// Address of the domain with the normalized name on the chain with the SLIP-44 coin type e.g. 60 for Ethereum,
+	/// `null` if it has none.
+	#[rpc(name = "names_address")]
+	fn address(&self, name: String, coin_type: u32, at: Option<BlockHash>) -> Result<Option<Bytes>>;
+
+	/// EIP-1577 content hash of the domain with the normalized name, which gateways serve the website of the domain from,
+	/// `null` if it has none.
+	#[rpc(name = "names_contentHash")]
+	fn content_hash(&self, name: String, at: Option<BlockHash>) -> Result<Option<Bytes>>;
+
+	/// Auction of the domain with the namehash, `null` if it is not in an auction.
+	#[rpc(name = "names_auctionStatus")]
+	fn auction_status(&self, domain_hash: Hash, at: Option<BlockHash>) -> Result<Option<AuctionStatus>>;
+
+	/// Compute the namehash of a normalized domain name, which is the key of the domain in the runtime.
+	#[rpc(name = "names_computeHash")]
+	fn compute_hash(&self, name: String) -> Result<Hash>;
+}
+
+/// Implementation of the name service RPC.
+pub struct Names<C> {
+	client: Arc<C>,
+}
+
+impl<C> Names<C> {
+	/// Create the name service RPC on top of the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Names { client }
+	}
+}
+
+impl<C> Names<C> where C: HeaderBackend<Block> {
+	fn at(&self, at: Option<Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+impl<C> NamesApi<Hash> for Names<C> where
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi + HeaderBackend<Block>,
+	C::Api: NameServiceApi<Block>,
+{
+	fn resolve(&self, name: String, at: Option<Hash>) -> Result<Option<Domain>> {
+		name::validate_owner(name.as_bytes()).map_err(invalid_name)?;
+		let at = self.at(at);
+		let info = self.client.runtime_api().resolve(&at, name.into_bytes()).map_err(runtime_error)?;
+		Ok(info.map(Domain::from))
+	}
+
+	fn reverse(&self, account: AccountId, at: Option<Hash>) -> Result<Option<Domain>> {
+		let at = self.at(at);
+		let api = self.client.runtime_api();
+		let primary = match api.primary_name(&at, account).map_err(runtime_error)? {
+			Some(primary) => primary,
+			None => return Ok(None),
+		};
+		let info = api.resolve_hash(&at, primary).map_err(runtime_error)?;
+		Ok(info.map(Domain::from))
+	}
+
+	fn list_by_owner(&self, account: AccountId, start: Option<u64>, limit: Option<u64>, at: Option<Hash>) -> Result<Vec<Hash>> {
+		let at = self.at(at);
+		let (start, limit) = (start.unwrap_or(0), limit.unwrap_or(MAX_PAGE_SIZE));
+		self.client.runtime_api().reverse(&at, account, start, limit).map_err(runtime_error)
+	}
+
+	fn address(&self, name: String, coin_type: u32, at: Option<Hash>) -> Result<Option<Bytes>> {
+		name::validate_owner(name.as_bytes()).map_err(invalid_name)?;
+		let at = self.at(at);
+		let domain_hash = name::namehash::<BlakeTwo256>(name.as_bytes());
+		let address = self.client.runtime_api().address(&at, domain_hash, coin_type).map_err(runtime_error)?;
+		Ok(address.map(Bytes))
+	}
+
+	fn content_hash(&self, name: String, at: Option<Hash>) -> Result<Option<Bytes>> {
+		name::validate_owner(name.as_bytes()).map_err(invalid_name)?;
+		let at = self.at(at);
+		let domain_hash = name::namehash::<BlakeTwo256>(name.as_bytes());
+		let hash = self.client.runtime_api().content_hash(&at, domain_hash).map_err(runtime_error)?;
+		Ok(hash.map(Bytes))
+	}
+
+	fn auction_status(&self, domain_hash: Hash, at: Option<Hash>) -> Result<Option<AuctionStatus>> {
+		let at = self.at(at);
+		let status = self.client.runtime_api().auction_status(&at, domain_hash).map_err(runtime_error)?;
+		Ok(status.map(AuctionStatus::from))
+	}
+
+	fn compute_hash(&self, name: String) -> Result<Hash> {
+		name::validate_owner(name.as_bytes()).map_err(invalid_name)?;
+		// Same hashing as `Hashing` of the runtime
+		Ok(name::namehash::<BlakeTwo256>(name.as_bytes()))
+	}
+}
+
+fn invalid_name(e: &'static str) -> Error {
+	Error {
+		code: ErrorCode::InvalidParams,
+		message: e.into(),
+		data: None,
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query the name service of the runtime".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
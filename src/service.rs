@@ -2,11 +2,12 @@
 
 use std::sync::Arc;
 use std::time::Duration;
-use substrate_client::LongestChain;
+use substrate_client::{LongestChain, blockchain::HeaderBackend};
 use babe;
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
 use futures::prelude::*;
-use node_template_runtime::{self, GenesisConfig, opaque::Block, RuntimeApi};
+use node_template_runtime::{self, GenesisConfig, opaque::Block, RuntimeApi, NameServiceApi};
+use sr_primitives::traits::ProvideRuntimeApi;
 use substrate_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use inherents::InherentDataProviders;
@@ -26,6 +27,19 @@ construct_simple_protocol! {
 	pub struct NodeProtocol where Block = Block { }
 }
 
+/// Custom RPC methods of the node on top of the default Substrate RPC.
+pub fn rpc_extensions<C, M>(client: Arc<C>) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: NameServiceApi<Block>,
+	M: jsonrpc_core::Metadata + Default,
+{
+	use crate::rpc::NamesApi;
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	io.extend_with(crate::rpc::Names::new(client).to_delegate());
+	io
+}
+
 /// Starts a `ServiceBuilder` for a full service.
 ///
 /// Use this macro if you don't actually need the full service, but just the builder in order to
@@ -44,6 +58,7 @@ macro_rules! new_full_start {
 			.with_transaction_pool(|config, client|
 				Ok(transaction_pool::txpool::Pool::new(config, transaction_pool::FullChainApi::new(client)))
 			)?
+			.with_rpc_extensions(|client, _pool| crate::service::rpc_extensions(client))?
 			.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
 				let select_chain = select_chain.take()
 					.ok_or_else(|| substrate_service::Error::SelectChainRequired)?;
@@ -80,7 +95,7 @@ macro_rules! new_full_start {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisConfig>)
+pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisConfig>, dns: Option<crate::dns::DnsConfig>)
 	-> Result<impl AbstractService, ServiceError>
 {
 
@@ -97,6 +112,12 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 		)?
 		.build()?;
 
+	if let Some(dns) = dns {
+		let dns = crate::dns::start(service.client(), dns)
+			.map_err(|e| ServiceError::Other(format!("Failed to start the DNS server: {}", e)))?;
+		service.spawn_task(Box::new(dns.select(service.on_exit()).then(|_| Ok(()))));
+	}
+
 	let (block_import, grandpa_link, babe_link) =
 		import_setup.take()
 			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
@@ -189,6 +210,7 @@ pub fn new_light<C: Send + Default + 'static>(config: Configuration<C, GenesisCo
 		.with_transaction_pool(|config, client|
 			Ok(TransactionPool::new(config, transaction_pool::FullChainApi::new(client)))
 		)?
+		.with_rpc_extensions(|client, _pool| rpc_extensions(client))?
 		.with_import_queue_and_fprb(|_config, client, backend, fetcher, _select_chain, _tx_pool| {
 			let fetch_checker = fetcher
 				.map(|fetcher| fetcher.checker().clone())